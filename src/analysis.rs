@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::board::{Board, Cell};
+use crate::rules::{play, RuleSet};
+use crate::solver::{dead_squares, solve};
+
+// weights of the parts of the difficulty score, relative to a single push
+const BOX_LINE_WEIGHT: usize = 2;
+const BOX_CHANGE_WEIGHT: usize = 4;
+
+#[derive(Serialize)]
+pub struct Analysis {
+    pub width: usize,
+    pub height: usize,
+    pub boxes: usize,
+    pub dead_squares: usize,
+    pub reachable_area: usize,
+    // the rest comes from the solver and is missing when it gave up
+    pub moves: Option<usize>,
+    pub pushes: Option<usize>,
+    pub box_lines: Option<usize>,
+    pub box_changes: Option<usize>,
+    pub difficulty: Option<usize>,
+    pub solution: Option<String>,
+}
+
+// Measures a level played by `rules`. `limit` is the number of positions the solver may look at.
+pub fn analyse(board: &Board, rules: RuleSet, limit: usize) -> Analysis {
+    let mut analysis = Analysis {
+        width: board.width,
+        height: board.height,
+        boxes: board.boxes.iter().filter(|block| block.is_some()).count(),
+        dead_squares: dead_squares(board).into_iter().filter(|&dead| dead).count(),
+        reachable_area: board.cells.iter().filter(|&&cell| cell == Cell::Floor).count(),
+        moves: None,
+        pushes: None,
+        box_lines: None,
+        box_changes: None,
+        difficulty: None,
+        solution: None,
+    };
+
+    if let Some(solution) = solve(board, rules, limit) {
+        let (box_lines, box_changes) = push_runs(board, rules, &solution.moves);
+        analysis.moves = Some(solution.move_count());
+        analysis.pushes = Some(solution.pushes);
+        analysis.box_lines = Some(box_lines);
+        analysis.box_changes = Some(box_changes);
+        analysis.difficulty = Some(
+            solution.pushes + box_lines * BOX_LINE_WEIGHT + box_changes * BOX_CHANGE_WEIGHT,
+        );
+        analysis.solution = Some(solution.moves);
+    }
+
+    analysis
+}
+
+// Box lines are runs of pushes of one box in one direction, box changes count how often the
+// pushed box is a different one than before.
+pub fn push_runs(board: &Board, rules: RuleSet, moves: &str) -> (usize, usize) {
+    // where the box next to the player ended up and which way it went
+    let mut last = None;
+    let (mut lines, mut changes) = (0, 0);
+
+    let mut board = board.clone();
+    let _ = play(&mut board, moves, rules, |_, step, _| {
+        let (from, to) = match step.pushed.last() {
+            Some(&pushed) => pushed,
+            None => return,
+        };
+
+        match last {
+            Some((cell, _)) if cell != from => {
+                changes += 1;
+                lines += 1;
+            }
+            Some((_, direction)) if direction != step.direction => lines += 1,
+            Some(_) => (),
+            None => lines += 1,
+        }
+        last = Some((to, step.direction));
+    });
+
+    (lines, changes)
+}
@@ -0,0 +1,206 @@
+use std::fs::{self, File};
+use std::path::Path;
+
+use gif::{Encoder, Frame, Repeat, SetParameter};
+use image::{Rgba, RgbaImage};
+use rusttype::{point, Font, Scale};
+
+use crate::asset_files::ThemeDefinition;
+use crate::board::Board;
+use crate::render::{colour, render, scale, TileImages};
+use crate::rules::{play, MoveError, RuleSet, Stats};
+
+// the solved position stays up at least this long before the animation starts over
+const END_PAUSE_MS: u32 = 1000;
+// 1 is the best and slowest colour reduction, 30 the fastest
+const QUANTIZE_SPEED: i32 = 10;
+
+pub struct ReplaySettings {
+    pub delay_ms: u32,
+    pub scale: f32,
+    // leave out the empty rows and columns around the walls
+    pub crop: bool,
+    pub rules: RuleSet,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        ReplaySettings {
+            delay_ms: 150,
+            scale: 1.,
+            crop: false,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+// The move and push counter drawn over the frames, in the theme's UI font and colour.
+pub struct Counter {
+    font: Font<'static>,
+    colour: Rgba<u8>,
+}
+
+impl Counter {
+    pub fn load(assets_dir: &Path, theme: &ThemeDefinition) -> Result<Counter, String> {
+        let path = assets_dir.join(&theme.font);
+        let bytes = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let font = Font::from_bytes(bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Ok(Counter {
+            font,
+            colour: colour(theme.text_colour),
+        })
+    }
+
+    fn draw(&self, image: &mut RgbaImage, stats: Stats, size: f32) {
+        let text = format!("Moves: {} Pushes: {}", stats.moves, stats.pushes);
+        let scale = Scale::uniform(size);
+        let margin = size / 4.;
+        let baseline = margin + self.font.v_metrics(scale).ascent;
+        let glyphs = self
+            .font
+            .layout(&text, scale, point(margin, baseline))
+            .collect::<Vec<_>>();
+
+        // a dark band behind the text keeps it readable on any tile
+        let right = glyphs
+            .iter()
+            .filter_map(|glyph| glyph.pixel_bounding_box())
+            .map(|bounds| bounds.max.x)
+            .max()
+            .unwrap_or(0) as f32;
+        let band_width = ((right + margin) as u32).min(image.width());
+        let band_height = ((size + margin * 2.) as u32).min(image.height());
+        for y in 0..band_height {
+            for x in 0..band_width {
+                blend(image.get_pixel_mut(x, y), Rgba([0, 0, 0, 255]), 0.5);
+            }
+        }
+
+        for glyph in glyphs {
+            let bounds = match glyph.pixel_bounding_box() {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+            glyph.draw(|x, y, coverage| {
+                let (x, y) = (x as i32 + bounds.min.x, y as i32 + bounds.min.y);
+                if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+                    blend(image.get_pixel_mut(x as u32, y as u32), self.colour, coverage);
+                }
+            });
+        }
+    }
+}
+
+fn blend(pixel: &mut Rgba<u8>, colour: Rgba<u8>, amount: f32) {
+    for channel in 0..3 {
+        let mixed = pixel[channel] as f32 * (1. - amount) + colour[channel] as f32 * amount;
+        pixel[channel] = mixed.round() as u8;
+    }
+}
+
+// Draws a frame for the starting position and one after every move, handing each to `each` as
+// soon as it is drawn. The moves are played through once first, so nothing is drawn when the
+// rules do not allow one of them.
+pub fn replay_frames(
+    board: &Board,
+    moves: &str,
+    tiles: &TileImages,
+    counter: Option<&Counter>,
+    settings: &ReplaySettings,
+    mut each: impl FnMut(RgbaImage),
+) -> Result<(), MoveError> {
+    let mut board = board.clone();
+    if settings.crop {
+        board.trim();
+    }
+    play(&mut board.clone(), moves, settings.rules, |_, _, _| ())?;
+
+    let frame = |board: &Board, stats: Stats| {
+        let mut image = render(board, tiles, false);
+        if settings.scale != 1. {
+            image = scale(&image, settings.scale);
+        }
+        if let Some(counter) = counter {
+            let size = (tiles.tile_size.1 as f32 * settings.scale / 2.).max(10.);
+            counter.draw(&mut image, stats, size);
+        }
+        image
+    };
+
+    each(frame(&board, Stats::default()));
+    play(&mut board, moves, settings.rules, |board, _, stats| each(frame(board, stats)))?;
+    Ok(())
+}
+
+// Writes a looping GIF one frame at a time, creating the file with the first frame. Each frame
+// is held back until the next one arrives so the last one can be shown longer. The first error
+// stops the writing and comes back from `finish`.
+pub struct GifWriter<'a> {
+    path: &'a Path,
+    delay_ms: u32,
+    encoder: Option<Encoder<File>>,
+    pending: Option<Frame<'static>>,
+    error: Option<String>,
+}
+
+impl<'a> GifWriter<'a> {
+    pub fn new(path: &'a Path, delay_ms: u32) -> GifWriter<'a> {
+        GifWriter {
+            path,
+            delay_ms,
+            encoder: None,
+            pending: None,
+            error: None,
+        }
+    }
+
+    pub fn push(&mut self, image: RgbaImage) {
+        if self.error.is_none() {
+            if let Err(e) = self.try_push(image) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn try_push(&mut self, image: RgbaImage) -> Result<(), String> {
+        if image.width() > u16::MAX as u32 || image.height() > u16::MAX as u32 {
+            return Err("the level is too large for a GIF, try a smaller --scale".to_string());
+        }
+        let (width, height) = (image.width() as u16, image.height() as u16);
+
+        if self.encoder.is_none() {
+            let file = File::create(self.path).map_err(|e| self.write_error(e))?;
+            let mut encoder = Encoder::new(file, width, height, &[]).map_err(|e| self.write_error(e))?;
+            encoder.set(Repeat::Infinite).map_err(|e| self.write_error(e))?;
+            self.encoder = Some(encoder);
+        }
+
+        let mut pixels = image.into_raw();
+        let frame = Frame::from_rgba_speed(width, height, &mut pixels, QUANTIZE_SPEED);
+        match self.pending.replace(frame) {
+            Some(previous) => self.write(previous, self.delay_ms),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&mut self, mut frame: Frame, delay_ms: u32) -> Result<(), String> {
+        // GIF delays count hundredths of a second
+        frame.delay = (delay_ms / 10).min(u16::MAX as u32) as u16;
+        let encoder = self.encoder.as_mut().expect("the encoder is created with the first frame");
+        let written = encoder.write_frame(&frame);
+        written.map_err(|e| self.write_error(e))
+    }
+
+    fn write_error(&self, e: std::io::Error) -> String {
+        format!("could not write {}: {}", self.path.display(), e)
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let last = self.pending.take().ok_or("there are no frames to write")?;
+        self.write(last, self.delay_ms.max(END_PAUSE_MS))
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+const REQUIRED_SPRITES: [&str; 7] = [
+    "player",
+    "box_blue",
+    "box_red",
+    "box_spot_blue",
+    "box_spot_red",
+    "wall",
+    "floor",
+];
+const REQUIRED_PREFABS: [&str; 3] = ["player", "box_blue", "box_red"];
+
+// The contents of `assets/manifest.ron`, all paths are relative to the assets directory.
+#[derive(Default, Deserialize, Serialize)]
+pub struct AssetManifest {
+    pub themes: Vec<String>,
+    pub sounds: Vec<String>,
+}
+
+// A look for the game: where its tiles, animations and font come from and the colours around them.
+// Colours are linear RGBA.
+#[derive(Deserialize, Serialize)]
+pub struct ThemeDefinition {
+    pub name: String,
+    pub tileset: String,
+    pub prefabs: BTreeMap<String, String>,
+    pub background: [f32; 4],
+    pub font: String,
+    pub text_colour: [f32; 4],
+    pub selected_colour: [f32; 4],
+    pub disabled_colour: [f32; 4],
+}
+
+impl ThemeDefinition {
+    // names the game looks up directly and that a theme therefore has to provide
+    pub fn missing_entries(&self, tileset: &TilesetDefinition) -> Vec<String> {
+        let sprites = REQUIRED_SPRITES
+            .iter()
+            .filter(|name| !tileset.sprites.contains_key(**name))
+            .map(|name| format!("sprite \"{}\"", name));
+        let prefabs = REQUIRED_PREFABS
+            .iter()
+            .filter(|name| !self.prefabs.contains_key(**name))
+            .map(|name| format!("prefab \"{}\"", name));
+
+        sprites.chain(prefabs).collect()
+    }
+}
+
+// One texture cut into a grid of tiles, with names for the tiles the game uses.
+#[derive(Default, Deserialize, Serialize)]
+pub struct TilesetDefinition {
+    pub texture: String,
+    pub tile_size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+    pub sprites: BTreeMap<String, usize>,
+}
+
+impl TilesetDefinition {
+    // an empty grid or a sprite past its end cannot be cut out of the texture
+    pub fn check(&self) -> Result<(), String> {
+        if self.tile_size.0 == 0 || self.tile_size.1 == 0 {
+            return Err(format!("tileset {} has an empty tile size", self.texture));
+        }
+        if self.columns == 0 || self.rows == 0 {
+            return Err(format!("tileset {} has no columns or rows", self.texture));
+        }
+        let tiles = self.columns as usize * self.rows as usize;
+        match self.sprites.iter().find(|(_, &index)| index >= tiles) {
+            Some((name, index)) => Err(format!(
+                "sprite \"{}\" is tile {} but tileset {} has only {} tiles",
+                name, index, self.texture, tiles
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+// The tile for a wall with the neighbours in `mask`, see `Board::wall_mask`. Tilesets without
+// autotile variants keep using the plain wall.
+pub fn wall_tile(mask: u8, has_tile: impl Fn(&str) -> bool) -> String {
+    let name = format!("wall_{}", mask);
+    if has_tile(&name) {
+        name
+    } else {
+        "wall".to_string()
+    }
+}
+
+// The directory holding `assets`, found like amethyst's `application_root_dir` finds it for the
+// game: the crate directory under cargo, the executable's directory otherwise.
+pub fn application_root_dir() -> io::Result<PathBuf> {
+    if let Some(manifest_dir) = env::var_os("CARGO_MANIFEST_DIR") {
+        return Ok(PathBuf::from(manifest_dir));
+    }
+    let exe = env::current_exe()?;
+    exe.parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the executable has no directory"))
+}
+
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    ron::de::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// theme and tileset paths are relative to the assets directory, like everything in the manifest
+pub fn load_theme(
+    assets_dir: &Path,
+    theme_path: &str,
+) -> Result<(ThemeDefinition, TilesetDefinition), String> {
+    let theme: ThemeDefinition = read_ron(&assets_dir.join(theme_path))?;
+    let tileset: TilesetDefinition = read_ron(&assets_dir.join(&theme.tileset))?;
+    tileset.check()?;
+
+    let missing = theme.missing_entries(&tileset);
+    if !missing.is_empty() {
+        return Err(format!(
+            "theme \"{}\" has no {}",
+            theme.name,
+            missing.join(", ")
+        ));
+    }
+
+    Ok((theme, tileset))
+}
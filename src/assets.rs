@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use amethyst::assets::{AssetStorage, Handle, Loader, Prefab, ProgressCounter, RonFormat};
+use amethyst::audio::{OggFormat, SourceHandle, WavFormat};
+use amethyst::prelude::*;
+use amethyst::renderer::{ImageFormat, Sprite, SpriteRender, SpriteSheet, Texture};
+use amethyst::ui::{FontHandle, TtfFormat};
+
+use crate::asset_files::{AssetManifest, ThemeDefinition, TilesetDefinition};
+use crate::settings::Settings;
+use crate::sokoban::MyPrefabData;
+
+#[derive(Clone)]
+pub struct Tileset {
+    pub sheet: Handle<SpriteSheet>,
+    pub sprites: HashMap<String, usize>,
+}
+
+impl Tileset {
+    fn load(
+        world: &World,
+        definition: &TilesetDefinition,
+        progress: &mut ProgressCounter,
+    ) -> Tileset {
+        let loader = world.read_resource::<Loader>();
+        let texture = loader.load(
+            definition.texture.as_str(),
+            ImageFormat::default(),
+            &mut *progress,
+            &world.read_resource::<AssetStorage<Texture>>(),
+        );
+
+        let (tile_width, tile_height) = definition.tile_size;
+        let (image_width, image_height) = (
+            tile_width * definition.columns,
+            tile_height * definition.rows,
+        );
+        let sprites = (0..definition.rows)
+            .flat_map(|row| (0..definition.columns).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                Sprite::from_pixel_values(
+                    image_width,
+                    image_height,
+                    tile_width,
+                    tile_height,
+                    column * tile_width,
+                    row * tile_height,
+                    [0., 0.],
+                    false,
+                    false,
+                )
+            })
+            .collect();
+
+        let sheet = loader.load_from_data(
+            SpriteSheet { texture, sprites },
+            &mut *progress,
+            &world.read_resource::<AssetStorage<SpriteSheet>>(),
+        );
+
+        Tileset {
+            sheet,
+            sprites: definition
+                .sprites
+                .iter()
+                .map(|(name, index)| (name.clone(), *index))
+                .collect(),
+        }
+    }
+
+    pub fn sprite(&self, name: &str) -> SpriteRender {
+        let sprite_number = *self
+            .sprites
+            .get(name)
+            .unwrap_or_else(|| panic!("sprite {} is missing from the tileset", name));
+
+        SpriteRender {
+            sprite_sheet: self.sheet.clone(),
+            sprite_number,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    pub tileset: Tileset,
+    pub prefabs: HashMap<String, Handle<Prefab<MyPrefabData>>>,
+    pub font: FontHandle,
+    pub background: [f32; 4],
+    pub text_colour: [f32; 4],
+    pub selected_colour: [f32; 4],
+    pub disabled_colour: [f32; 4],
+}
+
+impl Theme {
+    fn load(
+        world: &World,
+        definition: &ThemeDefinition,
+        tileset: &TilesetDefinition,
+        progress: &mut ProgressCounter,
+    ) -> Theme {
+        let tileset = Tileset::load(world, tileset, progress);
+        let loader = world.read_resource::<Loader>();
+
+        let prefabs = definition
+            .prefabs
+            .iter()
+            .map(|(name, path)| {
+                let prefab = loader.load(
+                    path.as_str(),
+                    RonFormat,
+                    &mut *progress,
+                    &world.read_resource(),
+                );
+                (name.clone(), prefab)
+            })
+            .collect();
+
+        let font = loader.load(
+            definition.font.as_str(),
+            TtfFormat,
+            &mut *progress,
+            &world.read_resource(),
+        );
+
+        Theme {
+            name: definition.name.clone(),
+            tileset,
+            prefabs,
+            font,
+            background: definition.background,
+            text_colour: definition.text_colour,
+            selected_colour: definition.selected_colour,
+            disabled_colour: definition.disabled_colour,
+        }
+    }
+
+    pub fn sprite(&self, name: &str) -> SpriteRender {
+        self.tileset.sprite(name)
+    }
+
+    pub fn prefab(&self, name: &str) -> Option<Handle<Prefab<MyPrefabData>>> {
+        self.prefabs.get(name).cloned()
+    }
+}
+
+// Handles to everything in the manifest.
+pub struct GameAssets {
+    pub themes: Vec<Theme>,
+    pub sounds: HashMap<String, SourceHandle>,
+}
+
+impl GameAssets {
+    pub fn load(
+        world: &World,
+        manifest: &AssetManifest,
+        themes: &[(ThemeDefinition, TilesetDefinition)],
+        progress: &mut ProgressCounter,
+    ) -> Self {
+        let themes = themes
+            .iter()
+            .map(|(theme, tileset)| Theme::load(world, theme, tileset, progress))
+            .collect();
+
+        let loader = world.read_resource::<Loader>();
+        let sounds = manifest
+            .sounds
+            .iter()
+            .map(|path| {
+                let sound = if path.ends_with(".ogg") {
+                    loader.load(path.as_str(), OggFormat, &mut *progress, &world.read_resource())
+                } else {
+                    loader.load(path.as_str(), WavFormat, &mut *progress, &world.read_resource())
+                };
+                (path.clone(), sound)
+            })
+            .collect();
+
+        GameAssets { themes, sounds }
+    }
+
+    // unknown names fall back to the first theme, so a renamed theme does not break old settings
+    pub fn theme(&self, name: &str) -> &Theme {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .unwrap_or(&self.themes[0])
+    }
+}
+
+pub fn current_theme(world: &World) -> Theme {
+    let name = world.read_resource::<Settings>().display.theme.clone();
+    world.read_resource::<GameAssets>().theme(&name).clone()
+}
@@ -0,0 +1,8 @@
+use crate::asset_files::wall_tile;
+use crate::assets::Theme;
+use crate::board::Board;
+
+// The sprite of the wall at `index`, picked from the walls next to it.
+pub fn wall_sprite(theme: &Theme, board: &Board, index: usize) -> String {
+    wall_tile(board.wall_mask(index), |name| theme.tileset.sprites.contains_key(name))
+}
@@ -0,0 +1,144 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+use serde::Serialize;
+use sokoban_tutorial::analysis::{analyse, Analysis};
+use sokoban_tutorial::level::LevelPack;
+use sokoban_tutorial::rules::RuleSet;
+
+const USAGE: &str =
+    "usage: sokoban-analyse [--json] [--sort] [--limit N] [--rules chain|classic] PACK...";
+const DEFAULT_LIMIT: usize = 1_000_000;
+
+#[derive(Serialize)]
+struct Report {
+    pack: String,
+    title: String,
+    #[serde(flatten)]
+    analysis: Analysis,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sokoban-analyse: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut json = false;
+    let mut sort = false;
+    let mut limit = DEFAULT_LIMIT;
+    let mut rules = RuleSet::default();
+    let mut paths = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--json" => json = true,
+            "--sort" => sort = true,
+            "--limit" => {
+                limit = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("--limit expects a number\n{}", USAGE))?
+            }
+            "--rules" => {
+                rules = args
+                    .next()
+                    .and_then(|name| RuleSet::parse(&name))
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut reports = Vec::new();
+    for path in &paths {
+        let pack = LevelPack::load(Path::new(path))
+            .map_err(|e| format!("could not read {}: {}", path, e))?;
+        pack.print_skipped();
+        for level in &pack.levels {
+            reports.push(Report {
+                pack: pack.name.clone(),
+                title: level.title.clone(),
+                analysis: analyse(&level.board, rules, limit),
+            });
+        }
+    }
+
+    // unsolved levels go last
+    if sort {
+        reports.sort_by_key(|report| report.analysis.difficulty.unwrap_or(usize::MAX));
+    }
+
+    if json {
+        let output = serde_json::to_string_pretty(&reports).map_err(|e| e.to_string())?;
+        println!("{}", output);
+    } else {
+        print_table(&reports);
+    }
+
+    Ok(())
+}
+
+fn print_table(reports: &[Report]) {
+    let optional = |value: Option<usize>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let header = [
+        "pack", "level", "size", "boxes", "dead", "area", "moves", "pushes", "lines", "changes",
+        "difficulty",
+    ];
+    let rows = reports
+        .iter()
+        .map(|report| {
+            let analysis = &report.analysis;
+            vec![
+                report.pack.clone(),
+                report.title.clone(),
+                format!("{}x{}", analysis.width, analysis.height),
+                analysis.boxes.to_string(),
+                analysis.dead_squares.to_string(),
+                analysis.reachable_area.to_string(),
+                optional(analysis.moves),
+                optional(analysis.pushes),
+                optional(analysis.box_lines),
+                optional(analysis.box_changes),
+                optional(analysis.difficulty),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain(std::iter::once(header[column].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+
+    let line = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", line(header.iter().map(|cell| cell.to_string()).collect()));
+    for row in rows {
+        println!("{}", line(row));
+    }
+}
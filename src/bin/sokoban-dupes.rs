@@ -0,0 +1,69 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use std::process;
+
+use sokoban_tutorial::canonical::{level_hash, symmetric_hash};
+use sokoban_tutorial::level::LevelPack;
+
+const USAGE: &str = "usage: sokoban-dupes [--symmetric] PACK...";
+
+// Lists levels that appear more than once across the given packs and exits with 1 if there are
+// any. With `--symmetric`, rotated and mirrored copies count as duplicates too.
+fn main() {
+    match run() {
+        Ok(true) => process::exit(1),
+        Ok(false) => (),
+        Err(e) => {
+            eprintln!("sokoban-dupes: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn run() -> Result<bool, String> {
+    let mut symmetric = false;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(false);
+            }
+            "--symmetric" => symmetric = true,
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut levels: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in &paths {
+        let pack = LevelPack::load(Path::new(path))
+            .map_err(|e| format!("could not read {}: {}", path, e))?;
+        pack.print_skipped();
+        for level in &pack.levels {
+            let hash = if symmetric {
+                symmetric_hash(&level.board)
+            } else {
+                level_hash(&level.board)
+            };
+            levels
+                .entry(hash)
+                .or_default()
+                .push(format!("{} #{} {}", pack.name, level.number, level.title));
+        }
+    }
+
+    let mut found = false;
+    for (hash, names) in levels.iter().filter(|(_, names)| names.len() > 1) {
+        found = true;
+        println!("{}", hash);
+        for name in names {
+            println!("  {}", name);
+        }
+    }
+
+    Ok(found)
+}
@@ -0,0 +1,83 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sokoban_tutorial::board::check_size;
+use sokoban_tutorial::generator::{describe, generate, GeneratorSettings};
+
+const USAGE: &str = "usage: sokoban-gen [--seed N] [--width N] [--height N] [--boxes N] \
+[--colours 1|2] [--difficulty N] [--attempts N] [--count N] [-o FILE]";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sokoban-gen: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut settings = GeneratorSettings::default();
+    let mut seed = None;
+    let mut count = 1;
+    let mut output = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{} expects a number, got {:?}", arg, value))
+        };
+
+        match arg.as_str() {
+            "--seed" => seed = Some(number()?),
+            "--width" => settings.width = number()? as usize,
+            "--height" => settings.height = number()? as usize,
+            "--boxes" => settings.boxes = number()? as usize,
+            "--colours" => settings.colours = number()? as usize,
+            "--difficulty" => settings.difficulty = Some(number()? as usize),
+            "--attempts" => settings.attempts = number()? as usize,
+            "--count" => count = number()?,
+            "-o" | "--output" => output = Some(value),
+            _ => return Err(format!("unknown option {}\n{}", arg, USAGE)),
+        }
+    }
+
+    check_size(settings.width, settings.height).map_err(|e| e.to_string())?;
+
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs())
+    });
+
+    let mut pack = String::new();
+    for n in 0..count {
+        let level_seed = seed.wrapping_add(n);
+        let generated = generate(&settings, level_seed)
+            .ok_or_else(|| format!("no level found for seed {}, try a larger room", level_seed))?;
+        pack.push_str(&format!(
+            "; Random {} ({})\n{}\n\n",
+            level_seed,
+            describe(&generated),
+            generated.board.to_xsb()
+        ));
+    }
+
+    match output {
+        Some(path) => fs::write(&path, pack).map_err(|e| format!("could not write {}: {}", path, e)),
+        None => {
+            print!("{}", pack);
+            Ok(())
+        }
+    }
+}
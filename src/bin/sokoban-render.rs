@@ -0,0 +1,89 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use sokoban_tutorial::level::LevelPack;
+use sokoban_tutorial::render::{find_theme, render, scale, TileImages};
+use sokoban_tutorial::rules::{play, RuleSet};
+
+const USAGE: &str = "usage: sokoban-render LEVELS -o FILE.png [--level N] [--moves LURD] \
+[--rules chain|classic] [--scale N] [--theme NAME] [--assets DIR] [--transparent]";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sokoban-render: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut input = None;
+    let mut output = None;
+    let mut level: usize = 1;
+    let mut factor = 1.;
+    let mut theme = None;
+    let mut assets_dir = PathBuf::from("assets");
+    let mut transparent = false;
+    let mut moves = String::new();
+    let mut rules = RuleSet::default();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "-o" | "--output" => output = Some(value()?),
+            "--level" => {
+                level = value()?
+                    .parse()
+                    .map_err(|_| format!("--level expects a level number\n{}", USAGE))?
+            }
+            "--moves" => moves = value()?,
+            "--rules" => {
+                rules = RuleSet::parse(&value()?)
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?
+            }
+            "--scale" => {
+                factor = value()?
+                    .parse()
+                    .ok()
+                    .filter(|&factor: &f32| factor > 0.)
+                    .ok_or_else(|| format!("--scale expects a positive number\n{}", USAGE))?
+            }
+            "--theme" => theme = Some(value()?),
+            "--assets" => assets_dir = PathBuf::from(value()?),
+            "--transparent" => transparent = true,
+            _ => input = Some(arg),
+        }
+    }
+    let (input, output) = match (input, output) {
+        (Some(input), Some(output)) => (input, output),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let pack = LevelPack::load(Path::new(&input))
+        .map_err(|e| format!("could not read {}: {}", input, e))?;
+    pack.print_skipped();
+    let info = pack.level(level)?;
+    let mut board = info.board.clone();
+    play(&mut board, &moves, rules, |_, _, _| ())
+        .map_err(|e| format!("{} level {}: {}", input, level, e))?;
+
+    let (theme, tileset) = find_theme(&assets_dir, theme.as_deref())?;
+    let tiles = TileImages::load(&assets_dir, &theme, &tileset)?;
+    let mut image = render(&board, &tiles, transparent);
+    if factor != 1. {
+        image = scale(&image, factor);
+    }
+
+    image
+        .save(&output)
+        .map_err(|e| format!("could not write {}: {}", output, e))
+}
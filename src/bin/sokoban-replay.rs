@@ -0,0 +1,102 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use sokoban_tutorial::animation::{replay_frames, Counter, GifWriter, ReplaySettings};
+use sokoban_tutorial::level::LevelPack;
+use sokoban_tutorial::render::{find_theme, TileImages};
+use sokoban_tutorial::rules::RuleSet;
+
+const USAGE: &str = "usage: sokoban-replay LEVELS (--moves LURD | --moves-file FILE) -o FILE.gif \
+[--level N] [--delay MS] [--scale N] [--counter] [--crop] [--rules chain|classic] [--theme NAME] \
+[--assets DIR]";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sokoban-replay: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut settings = ReplaySettings::default();
+    let mut input = None;
+    let mut moves = None;
+    let mut output = None;
+    let mut level: usize = 1;
+    let mut counter = false;
+    let mut theme = None;
+    let mut assets_dir = PathBuf::from("assets");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "-o" | "--output" => output = Some(value()?),
+            "--moves" => moves = Some(value()?),
+            "--moves-file" => {
+                let path = value()?;
+                let contents = fs::read_to_string(&path)
+                    .map_err(|e| format!("could not read {}: {}", path, e))?;
+                moves = Some(contents);
+            }
+            "--level" => {
+                level = value()?
+                    .parse()
+                    .map_err(|_| format!("--level expects a level number\n{}", USAGE))?
+            }
+            "--delay" => {
+                settings.delay_ms = value()?
+                    .parse()
+                    .map_err(|_| format!("--delay expects milliseconds\n{}", USAGE))?
+            }
+            "--scale" => {
+                settings.scale = value()?
+                    .parse()
+                    .ok()
+                    .filter(|&factor: &f32| factor > 0.)
+                    .ok_or_else(|| format!("--scale expects a positive number\n{}", USAGE))?
+            }
+            "--rules" => {
+                settings.rules = RuleSet::parse(&value()?)
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?
+            }
+            "--counter" => counter = true,
+            "--crop" => settings.crop = true,
+            "--theme" => theme = Some(value()?),
+            "--assets" => assets_dir = PathBuf::from(value()?),
+            _ => input = Some(arg),
+        }
+    }
+    let (input, moves, output) = match (input, moves, output) {
+        (Some(input), Some(moves), Some(output)) => (input, moves, output),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let pack = LevelPack::load(Path::new(&input))
+        .map_err(|e| format!("could not read {}: {}", input, e))?;
+    pack.print_skipped();
+    let info = pack.level(level)?;
+
+    let (theme, tileset) = find_theme(&assets_dir, theme.as_deref())?;
+    let tiles = TileImages::load(&assets_dir, &theme, &tileset)?;
+    let counter = if counter {
+        Some(Counter::load(&assets_dir, &theme)?)
+    } else {
+        None
+    };
+
+    let mut gif = GifWriter::new(Path::new(&output), settings.delay_ms);
+    replay_frames(&info.board, &moves, &tiles, counter.as_ref(), &settings, |frame| gif.push(frame))
+        .map_err(|e| format!("{} level {}: {}", input, level, e))?;
+    gif.finish()
+}
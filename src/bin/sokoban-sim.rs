@@ -0,0 +1,161 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use serde::Serialize;
+use sokoban_tutorial::level::LevelPack;
+use sokoban_tutorial::replay::{Replay, ReplayError};
+use sokoban_tutorial::rules::{Game, RuleSet};
+
+const USAGE: &str = "usage: sokoban-sim LEVELS (--moves LURD | --moves-file FILE | --replay FILE) \
+[--level N] [--rules chain|classic] [--json] [--expect-solved]";
+
+#[derive(Serialize)]
+struct Report {
+    pack: String,
+    title: String,
+    rules: String,
+    moves: usize,
+    pushes: usize,
+    solved: bool,
+    // the position reached, up to the illegal move if there was one
+    board: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Illegal>,
+}
+
+#[derive(Serialize)]
+struct Illegal {
+    step: usize,
+    letter: char,
+    reason: String,
+}
+
+// Plays a move string or a replay on a level and prints where it ends up. Exits with 1 when a move
+// is not allowed, or with `--expect-solved` when the level is not solved at the end, and 2 on
+// errors, including a replay recorded on another level.
+fn main() {
+    match run() {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("sokoban-sim: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn run() -> Result<bool, String> {
+    let mut input = None;
+    let mut moves = None;
+    let mut replay = None;
+    let mut level: usize = 1;
+    let mut rules = None;
+    let mut json = false;
+    let mut expect_solved = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(true);
+            }
+            "--moves" => moves = Some(value()?),
+            "--moves-file" => {
+                let path = value()?;
+                let contents = fs::read_to_string(&path)
+                    .map_err(|e| format!("could not read {}: {}", path, e))?;
+                moves = Some(contents);
+            }
+            "--replay" => {
+                let path = value()?;
+                let loaded = Replay::load(Path::new(&path)).map_err(|e| format!("{}: {}", path, e))?;
+                replay = Some(loaded);
+            }
+            "--level" => {
+                level = value()?
+                    .parse()
+                    .map_err(|_| format!("--level expects a level number\n{}", USAGE))?
+            }
+            "--rules" => {
+                let parsed = RuleSet::parse(&value()?)
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?;
+                rules = Some(parsed);
+            }
+            "--json" => json = true,
+            "--expect-solved" => expect_solved = true,
+            _ => input = Some(arg),
+        }
+    }
+    // a bare move string and a replay are played the same way
+    let (input, mut replay) = match (input, moves, replay) {
+        (Some(input), Some(moves), None) => (input, Replay::from_moves(&moves)),
+        (Some(input), None, Some(replay)) => (input, replay),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let pack = LevelPack::load(Path::new(&input))
+        .map_err(|e| format!("could not read {}: {}", input, e))?;
+    pack.print_skipped();
+    let info = pack.level(level)?;
+
+    // a replay brings its own rules, which --rules overrides
+    if let Some(rules) = rules {
+        replay.rules = rules;
+    }
+
+    let mut game = Game::new(info.board.clone(), replay.rules);
+    let error = match replay.validate(&mut game) {
+        Ok(()) => None,
+        Err(ReplayError::IllegalMove { event, letter, reason }) => Some(Illegal {
+            step: event,
+            letter,
+            reason: reason.to_string(),
+        }),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let report = Report {
+        pack: pack.name.clone(),
+        title: info.title.clone(),
+        rules: replay.rules.to_string(),
+        moves: game.stats.moves,
+        pushes: game.stats.pushes,
+        solved: game.is_solved(),
+        board: game.board.to_xsb(),
+        error,
+    };
+
+    if json {
+        let output = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        println!("{}", output);
+    } else {
+        print_report(&report);
+    }
+
+    Ok(report.error.is_none() && (report.solved || !expect_solved))
+}
+
+fn print_report(report: &Report) {
+    println!("{}", report.board);
+    println!();
+    println!(
+        "moves: {} pushes: {} solved: {}",
+        report.moves,
+        report.pushes,
+        if report.solved { "yes" } else { "no" }
+    );
+    if let Some(error) = &report.error {
+        println!(
+            "illegal move {} ({}) under {} rules: {}",
+            error.step, error.letter, report.rules, error.reason
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use sokoban_tutorial::asset_files::application_root_dir;
+use sokoban_tutorial::board::{BoxColour, Cell};
+use sokoban_tutorial::level::{LevelPack, LevelPacks, LevelRef};
+use sokoban_tutorial::replay::{Action, Recorder};
+use sokoban_tutorial::rules::{Direction, Game, RuleSet};
+
+const USAGE: &str =
+    "usage: sokoban-tui [--rules chain|classic] [--level N] [--record DIR] [--player NAME] [PACK...]";
+const HELP: &str = "arrows/wasd move  u undo  y redo  r restart  n/p next/previous level  q quit";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("sokoban-tui: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut rules = RuleSet::default();
+    let mut start: usize = 1;
+    let mut paths = Vec::new();
+    let mut record = None;
+    let mut player = env::var("USER").unwrap_or_else(|_| "player".to_string());
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            "--rules" => {
+                rules = args
+                    .next()
+                    .and_then(|name| RuleSet::parse(&name))
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?
+            }
+            "--level" => {
+                start = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| format!("--level expects a level number\n{}", USAGE))?
+            }
+            "--record" => {
+                let dir = args
+                    .next()
+                    .ok_or_else(|| format!("--record needs a directory\n{}", USAGE))?;
+                record = Some(PathBuf::from(dir));
+            }
+            "--player" => {
+                player = args
+                    .next()
+                    .ok_or_else(|| format!("--player needs a name\n{}", USAGE))?
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    // the game's own levels unless packs are given
+    let packs = if paths.is_empty() {
+        let dir = application_root_dir()
+            .map_err(|e| format!("could not find the game's levels: {}, name the packs to play\n{}", e, USAGE))?
+            .join("assets")
+            .join("levels");
+        LevelPacks::load(&dir).map_err(|e| {
+            format!("could not read {}: {}, name the packs to play\n{}", dir.display(), e, USAGE)
+        })?
+    } else {
+        let packs = paths
+            .iter()
+            .map(|path| {
+                LevelPack::load(Path::new(path)).map_err(|e| format!("could not read {}: {}", path, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        LevelPacks { packs }
+    };
+
+    for pack in &packs.packs {
+        pack.print_skipped();
+    }
+
+    let levels = packs.iter().collect::<Vec<_>>();
+    if levels.is_empty() {
+        return Err("there are no playable levels".to_string());
+    }
+
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide).map_err(|e| e.to_string())?;
+
+    let recording = record.as_deref().map(|dir| (dir, player.as_str()));
+    let result = play(
+        &mut stdout,
+        &packs,
+        &levels,
+        start.saturating_sub(1),
+        rules,
+        recording,
+    );
+
+    // put the terminal back even when playing failed
+    execute!(stdout, ResetColor, cursor::Show, terminal::LeaveAlternateScreen).ok();
+    terminal::disable_raw_mode().ok();
+    result.map_err(|e| e.to_string())
+}
+
+fn play(
+    out: &mut impl Write,
+    packs: &LevelPacks,
+    levels: &[LevelRef],
+    start: usize,
+    rules: RuleSet,
+    // the directory replays are saved to and the player they are saved for
+    recording: Option<(&Path, &str)>,
+) -> io::Result<()> {
+    let mut current = start.min(levels.len() - 1);
+    let new_game = |current: usize| {
+        let info = packs.get(levels[current]).expect("levels come from the packs");
+        let recorder = recording
+            .map(|(_, player)| Recorder::new(&info.board, &info.title, rules, player));
+        (Game::new(info.board.clone(), rules), recorder)
+    };
+    let (mut game, mut recorder) = new_game(current);
+    let dir = recording.map(|(dir, _)| dir);
+
+    loop {
+        draw(out, packs, levels[current], &game)?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        let solved = game.is_solved();
+
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => return save(dir, recorder, &game),
+            KeyEvent { code, .. } => match code {
+                KeyCode::Char('q') | KeyCode::Esc => return save(dir, recorder, &game),
+                KeyCode::Char('u') | KeyCode::Char('z') | KeyCode::Backspace => {
+                    if game.undo() {
+                        note(&mut recorder, Action::Undo);
+                    }
+                }
+                KeyCode::Char('y') => {
+                    if game.redo() {
+                        note(&mut recorder, Action::Redo);
+                    }
+                }
+                KeyCode::Char('r') => {
+                    game.restart();
+                    note(&mut recorder, Action::Restart);
+                }
+                KeyCode::Char('n') | KeyCode::Enter if current + 1 < levels.len() => {
+                    save(dir, recorder, &game)?;
+                    current += 1;
+                    (game, recorder) = new_game(current);
+                }
+                KeyCode::Char('p') if current > 0 => {
+                    save(dir, recorder, &game)?;
+                    current -= 1;
+                    (game, recorder) = new_game(current);
+                }
+                _ => {
+                    // like the game, a solved level takes no more moves
+                    if let Some(direction) = direction(code).filter(|_| !solved) {
+                        if let Ok(step) = game.step(direction) {
+                            note(&mut recorder, Action::Move(direction.to_char(step.is_push())));
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn note(recorder: &mut Option<Recorder>, action: Action) {
+    if let Some(recorder) = recorder {
+        recorder.record(action);
+    }
+}
+
+// writes the replay of the level being left, named by the level hash and start time
+fn save(dir: Option<&Path>, recorder: Option<Recorder>, game: &Game) -> io::Result<()> {
+    let (dir, recorder) = match (dir, recorder) {
+        (Some(dir), Some(recorder)) if !recorder.replay.events.is_empty() => (dir, recorder),
+        _ => return Ok(()),
+    };
+    let replay = recorder.finish(game);
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}-{}.ron", replay.level, replay.started));
+    replay.save(&path).map_err(io::Error::other)
+}
+
+fn direction(code: KeyCode) -> Option<Direction> {
+    match code {
+        KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => Some(Direction::Up),
+        KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('l') => Some(Direction::Right),
+        KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => Some(Direction::Down),
+        KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('h') => Some(Direction::Left),
+        _ => None,
+    }
+}
+
+fn colour(colour: BoxColour) -> Color {
+    match colour {
+        BoxColour::Blue => Color::Blue,
+        BoxColour::Red => Color::Red,
+    }
+}
+
+// every cell is two characters wide so the board keeps its shape
+fn draw(out: &mut impl Write, packs: &LevelPacks, level: LevelRef, game: &Game) -> io::Result<()> {
+    let pack = &packs.packs[level.pack];
+    let title = &pack.levels[level.index].title;
+    let board = &game.board;
+
+    queue!(
+        out,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0),
+        SetAttribute(Attribute::Bold),
+        Print(format!(
+            "{} - {} ({}/{})",
+            pack.display_name(),
+            title,
+            level.index + 1,
+            pack.levels.len()
+        )),
+        SetAttribute(Attribute::Reset),
+    )?;
+
+    for row in 0..board.height {
+        queue!(out, cursor::MoveTo(0, row as u16 + 2))?;
+        for column in 0..board.width {
+            let index = board.index(column, row);
+            let goal = board.goals[index];
+            let (text, fg, reverse) = match (board.cells[index], board.boxes[index]) {
+                (Cell::Wall, _) => ("██", Color::DarkGrey, false),
+                (Cell::Outside, _) => ("  ", Color::Reset, false),
+                _ if board.player == Some(index) => ("@@", Color::Yellow, false),
+                (_, Some(block)) => ("[]", colour(block), goal == Some(block)),
+                (_, None) => match goal {
+                    Some(goal) => ("··", colour(goal), false),
+                    None => ("  ", Color::Reset, false),
+                },
+            };
+
+            if reverse {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(out, SetForegroundColor(fg), Print(text), ResetColor)?;
+            if reverse {
+                queue!(out, SetAttribute(Attribute::NoReverse))?;
+            }
+        }
+    }
+
+    let status = if game.is_solved() {
+        "Solved! n for the next level".to_string()
+    } else {
+        format!(
+            "Moves: {} Pushes: {}",
+            game.stats.moves, game.stats.pushes
+        )
+    };
+    let footer = board.height as u16 + 3;
+    queue!(
+        out,
+        cursor::MoveTo(0, footer),
+        Print(status),
+        cursor::MoveTo(0, footer + 1),
+        SetForegroundColor(Color::DarkGrey),
+        Print(format!("{}  ({} pushes)", HELP, game.rules)),
+        ResetColor,
+    )?;
+
+    out.flush()
+}
@@ -0,0 +1,94 @@
+use std::env;
+use std::path::Path;
+use std::process;
+
+use sokoban_tutorial::level::LevelPack;
+use sokoban_tutorial::rules::RuleSet;
+use sokoban_tutorial::solutions::{load, verify};
+
+const USAGE: &str = "usage: sokoban-verify [--rules chain|classic] PACK SOLUTIONS";
+
+// Checks every solution in SOLUTIONS against the levels of PACK and exits with 1 when one is
+// illegal, does not solve its level, is worse than the stored best or names an unknown level or a
+// title several levels share, and when a level of PACK cannot be played.
+fn main() {
+    match run() {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("sokoban-verify: {}", e);
+            process::exit(2);
+        }
+    }
+}
+
+fn run() -> Result<bool, String> {
+    let mut rules = RuleSet::default();
+    let mut paths = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(true);
+            }
+            "--rules" => {
+                rules = args
+                    .next()
+                    .and_then(|name| RuleSet::parse(&name))
+                    .ok_or_else(|| format!("--rules expects chain or classic\n{}", USAGE))?
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let (pack_path, solutions_path) = match paths.as_slice() {
+        [pack, solutions] => (pack, solutions),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let pack = LevelPack::load(Path::new(pack_path))
+        .map_err(|e| format!("could not read {}: {}", pack_path, e))?;
+    let solutions = load(Path::new(solutions_path))?;
+
+    let mut failures = 0;
+    let mut solved = vec![false; pack.levels.len()];
+    for solution in &solutions {
+        match solution.find(&pack.levels) {
+            Ok(index) => {
+                let level = &pack.levels[index];
+                let verdict = verify(&level.board, solution, rules);
+                if verdict.is_failure() {
+                    failures += 1;
+                }
+                solved[index] = true;
+                println!("{}: {}", level.title, verdict);
+            }
+            Err(e) => {
+                failures += 1;
+                println!("{}: {}", solution.level, e);
+            }
+        }
+    }
+
+    // not a failure, packs may hold levels nobody has solved yet
+    for (level, solved) in pack.levels.iter().zip(solved) {
+        if !solved {
+            println!("{}: no solution", level.title);
+        }
+    }
+
+    // a level that cannot be played has to be fixed before anyone can solve it
+    for skipped in &pack.skipped {
+        println!("{}: cannot be played, {}", skipped.title, skipped.error);
+    }
+
+    println!(
+        "{} of {} solutions failed, {} levels cannot be played ({} rules)",
+        failures,
+        solutions.len(),
+        pack.skipped.len(),
+        rules
+    );
+    Ok(failures == 0 && pack.skipped.is_empty())
+}
@@ -0,0 +1,454 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxColour {
+    Red,
+    Blue,
+}
+
+impl fmt::Display for BoxColour {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            BoxColour::Red => "red",
+            BoxColour::Blue => "blue",
+        })
+    }
+}
+
+pub const COLOURS: [BoxColour; 2] = [BoxColour::Blue, BoxColour::Red];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Outside,
+    Floor,
+    Wall,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelError {
+    UnknownTile { row: usize, column: usize, tile: String },
+    BadCount { row: usize, column: usize, count: String },
+    TooLarge { width: usize, height: usize },
+    MultiplePlayers,
+    NoPlayer,
+    NoBoxes,
+    Open,
+    Unreachable { x: usize, y: usize },
+    BoxCount { colour: BoxColour, boxes: usize, goals: usize },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::UnknownTile { row, column, tile } => write!(
+                fmt,
+                "unknown tile {:?} in row {}, column {}",
+                tile,
+                row + 1,
+                column + 1
+            ),
+            LevelError::BadCount { row, column, count } => write!(
+                fmt,
+                "run length {} in row {}, column {} is not a number of tiles",
+                count,
+                row + 1,
+                column + 1
+            ),
+            LevelError::TooLarge { width, height } => {
+                write!(fmt, "level is {}x{}, at most 255x255 is supported", width, height)
+            }
+            LevelError::MultiplePlayers => write!(fmt, "more than one player"),
+            LevelError::NoPlayer => write!(fmt, "no player"),
+            LevelError::NoBoxes => write!(fmt, "no boxes"),
+            LevelError::Open => write!(fmt, "the player can walk off the edge of the level"),
+            LevelError::Unreachable { x, y } => {
+                write!(fmt, "box or spot at {}, {} is outside the walls", x + 1, y + 1)
+            }
+            LevelError::BoxCount {
+                colour,
+                boxes,
+                goals,
+            } => write!(fmt, "{} {} boxes but {} {} spots", boxes, colour, goals, colour),
+        }
+    }
+}
+
+impl Error for LevelError {}
+
+// A level as plain data, independent of the ECS. Cells are stored row by row with row 0 at the
+// top, the way the level is written down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<Cell>,
+    pub goals: Vec<Option<BoxColour>>,
+    pub boxes: Vec<Option<BoxColour>>,
+    pub player: Option<usize>,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Board {
+        Board {
+            width,
+            height,
+            cells: vec![Cell::Outside; width * height],
+            goals: vec![None; width * height],
+            boxes: vec![None; width * height],
+            player: None,
+        }
+    }
+
+    // Reads the standard Sokoban text format (`#` wall, `@` player, `$` box, `.` goal, `*` box
+    // on goal, `+` player on goal, blank for floor and outside). Standard boxes and goals are
+    // blue; `R`, `r`, `X` and `p` are red box, red goal, red box on red goal and player on red
+    // goal, `%` is a blue box on a red goal and `&` a red box on a blue goal. Rows may be
+    // run-length encoded. Maps without any `#` are read as the older format of
+    // space separated tokens (`W`, `.`, `P`, `BB`, `RB`, `BS`, `RS`, `N`).
+    //
+    // Floor is flood filled from the player, so blank cells outside the walls stay outside.
+    pub fn parse(map: &str) -> Result<Board, LevelError> {
+        let rows = if map.contains('#') {
+            parse_standard(map)?
+        } else {
+            parse_tokens(map)?
+        };
+
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let height = rows.len();
+        check_size(width, height)?;
+
+        let mut board = Board::new(width, height);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let index = board.index(x, y);
+                board.cells[index] = if tile.wall { Cell::Wall } else { Cell::Floor };
+                board.goals[index] = tile.goal;
+                board.boxes[index] = tile.block;
+                if tile.player {
+                    if board.player.is_some() {
+                        return Err(LevelError::MultiplePlayers);
+                    }
+                    board.player = Some(index);
+                }
+            }
+        }
+
+        board.fill_floor();
+        Ok(board)
+    }
+
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    // The cells next to `index` in the order up, right, down, left; `None` past the edge.
+    pub fn neighbours(&self, index: usize) -> [Option<usize>; 4] {
+        let (x, y) = self.position(index);
+        [
+            if y > 0 { Some(index - self.width) } else { None },
+            if x + 1 < self.width { Some(index + 1) } else { None },
+            if y + 1 < self.height { Some(index + self.width) } else { None },
+            if x > 0 { Some(index - 1) } else { None },
+        ]
+    }
+
+    // 1, 2, 4 and 8 for a wall above, right, below and left of `index`, as the tilesets number
+    // their walls.
+    pub fn wall_mask(&self, index: usize) -> u8 {
+        self.neighbours(index)
+            .iter()
+            .enumerate()
+            .filter(|(_, neighbour)| neighbour.is_some_and(|neighbour| self.cells[neighbour] == Cell::Wall))
+            .fold(0, |mask, (direction, _)| mask | 1 << direction)
+    }
+
+    // Every cell that is not a wall and can be reached from `start`, ignoring boxes.
+    pub fn region(&self, start: usize) -> Vec<bool> {
+        let mut seen = vec![false; self.cells.len()];
+        let mut queue = VecDeque::new();
+        if self.cells[start] != Cell::Wall {
+            seen[start] = true;
+            queue.push_back(start);
+        }
+
+        while let Some(index) = queue.pop_front() {
+            for next in self.neighbours(index).iter().flatten() {
+                if !seen[*next] && self.cells[*next] != Cell::Wall {
+                    seen[*next] = true;
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        seen
+    }
+
+    // Turns the cells the player can reach into floor and every other non-wall cell into
+    // outside. Does nothing without a player.
+    pub fn fill_floor(&mut self) {
+        let player = match self.player {
+            Some(player) => player,
+            None => return,
+        };
+
+        let region = self.region(player);
+        for (cell, inside) in self.cells.iter_mut().zip(region) {
+            if *cell != Cell::Wall {
+                *cell = if inside { Cell::Floor } else { Cell::Outside };
+            }
+        }
+    }
+
+    // Everything that keeps the level from being playable, empty when it can be played.
+    pub fn errors(&self) -> Vec<LevelError> {
+        let mut errors = Vec::new();
+
+        if let Err(error) = check_size(self.width, self.height) {
+            errors.push(error);
+        }
+
+        match self.player {
+            Some(player) => {
+                let region = self.region(player);
+                let open = region.iter().enumerate().any(|(index, &inside)| {
+                    let (x, y) = self.position(index);
+                    inside && (x == 0 || y == 0 || x + 1 == self.width || y + 1 == self.height)
+                });
+                if open {
+                    errors.push(LevelError::Open);
+                }
+
+                for (index, inside) in region.into_iter().enumerate() {
+                    if !inside && (self.boxes[index].is_some() || self.goals[index].is_some()) {
+                        let (x, y) = self.position(index);
+                        errors.push(LevelError::Unreachable { x, y });
+                    }
+                }
+            }
+            None => errors.push(LevelError::NoPlayer),
+        }
+
+        if self.boxes.iter().all(Option::is_none) {
+            errors.push(LevelError::NoBoxes);
+        }
+
+        for &colour in COLOURS.iter() {
+            let count = |cells: &[Option<BoxColour>]| {
+                cells.iter().filter(|&&cell| cell == Some(colour)).count()
+            };
+            let boxes = count(&self.boxes);
+            let goals = count(&self.goals);
+            if boxes != goals {
+                errors.push(LevelError::BoxCount {
+                    colour,
+                    boxes,
+                    goals,
+                });
+            }
+        }
+
+        errors
+    }
+
+    // Keeps the top left of the level; anything past the new size is dropped.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = self.crop(0, 0, width, height);
+    }
+
+    // Drops the rows and columns along the edges that are entirely outside.
+    pub fn trim(&mut self) {
+        let used = (0..self.cells.len())
+            .filter(|&index| self.cells[index] != Cell::Outside)
+            .map(|index| self.position(index));
+
+        let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+        for (x, y) in used {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+
+        if left <= right && top <= bottom {
+            *self = self.crop(left, top, right + 1 - left, bottom + 1 - top);
+        }
+    }
+
+    // The `width` by `height` part of the level starting at `left`, `top`, padded with outside.
+    fn crop(&self, left: usize, top: usize, width: usize, height: usize) -> Board {
+        let mut cropped = Board::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = (left + x, top + y);
+                if from_x >= self.width || from_y >= self.height {
+                    continue;
+                }
+
+                let (from, to) = (self.index(from_x, from_y), cropped.index(x, y));
+                cropped.cells[to] = self.cells[from];
+                cropped.goals[to] = self.goals[from];
+                cropped.boxes[to] = self.boxes[from];
+                if self.player == Some(from) {
+                    cropped.player = Some(to);
+                }
+            }
+        }
+        cropped
+    }
+
+    // The level in the standard text format, see `parse`.
+    pub fn to_xsb(&self) -> String {
+        let mut rows = Vec::with_capacity(self.height);
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| self.tile_char(self.index(x, y)))
+                .collect();
+            rows.push(row.trim_end().to_string());
+        }
+        rows.join("\n")
+    }
+
+    fn tile_char(&self, index: usize) -> char {
+        if self.cells[index] == Cell::Wall {
+            return '#';
+        }
+
+        match (self.player == Some(index), self.boxes[index], self.goals[index]) {
+            (true, _, None) => '@',
+            (true, _, Some(BoxColour::Blue)) => '+',
+            (true, _, Some(BoxColour::Red)) => 'p',
+            (false, Some(BoxColour::Blue), Some(BoxColour::Blue)) => '*',
+            (false, Some(BoxColour::Red), Some(BoxColour::Red)) => 'X',
+            (false, Some(BoxColour::Blue), Some(BoxColour::Red)) => '%',
+            (false, Some(BoxColour::Red), Some(BoxColour::Blue)) => '&',
+            (false, Some(BoxColour::Blue), None) => '$',
+            (false, Some(BoxColour::Red), None) => 'R',
+            (false, None, Some(BoxColour::Blue)) => '.',
+            (false, None, Some(BoxColour::Red)) => 'r',
+            (false, None, None) => ' ',
+        }
+    }
+}
+
+// Positions are stored as `u8` once the level is loaded into the game.
+pub fn check_size(width: usize, height: usize) -> Result<(), LevelError> {
+    if width > u8::MAX as usize || height > u8::MAX as usize {
+        return Err(LevelError::TooLarge { width, height });
+    }
+    Ok(())
+}
+
+#[derive(Default, Clone, Copy)]
+struct ParsedTile {
+    wall: bool,
+    player: bool,
+    block: Option<BoxColour>,
+    goal: Option<BoxColour>,
+}
+
+fn parse_standard(map: &str) -> Result<Vec<Vec<ParsedTile>>, LevelError> {
+    let lines = map
+        .lines()
+        .flat_map(|line| line.split('|'))
+        .filter(|line| !line.trim().is_empty());
+
+    let mut rows = Vec::new();
+    for (row, line) in lines.enumerate() {
+        let mut tiles = Vec::new();
+        let mut count = String::new();
+
+        for c in line.trim_end().chars() {
+            if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            }
+
+            let tile = standard_tile(c).ok_or_else(|| LevelError::UnknownTile {
+                row,
+                column: tiles.len(),
+                tile: c.to_string(),
+            })?;
+            let repeat = if count.is_empty() {
+                1
+            } else {
+                count.parse::<usize>().map_err(|_| LevelError::BadCount {
+                    row,
+                    column: tiles.len(),
+                    count: count.clone(),
+                })?
+            };
+            count.clear();
+
+            // checked before expanding the run, so a huge count cannot take all the memory
+            let width = tiles.len().saturating_add(repeat);
+            check_size(width, row + 1)?;
+            tiles.extend(std::iter::repeat_n(tile, repeat));
+        }
+
+        rows.push(tiles);
+    }
+
+    Ok(rows)
+}
+
+fn standard_tile(c: char) -> Option<ParsedTile> {
+    let blue = Some(BoxColour::Blue);
+    let red = Some(BoxColour::Red);
+    let tile = ParsedTile::default();
+
+    Some(match c {
+        ' ' | '-' | '_' => tile,
+        '#' => ParsedTile { wall: true, ..tile },
+        '@' => ParsedTile { player: true, ..tile },
+        '+' => ParsedTile { player: true, goal: blue, ..tile },
+        'p' => ParsedTile { player: true, goal: red, ..tile },
+        '$' => ParsedTile { block: blue, ..tile },
+        '*' => ParsedTile { block: blue, goal: blue, ..tile },
+        '.' => ParsedTile { goal: blue, ..tile },
+        'R' => ParsedTile { block: red, ..tile },
+        'X' => ParsedTile { block: red, goal: red, ..tile },
+        'r' => ParsedTile { goal: red, ..tile },
+        '%' => ParsedTile { block: blue, goal: red, ..tile },
+        '&' => ParsedTile { block: red, goal: blue, ..tile },
+        _ => return None,
+    })
+}
+
+fn parse_tokens(map: &str) -> Result<Vec<Vec<ParsedTile>>, LevelError> {
+    let tile = ParsedTile::default();
+
+    map.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(row, line)| {
+            line.split_whitespace()
+                .enumerate()
+                .map(|(column, token)| {
+                    Ok(match token {
+                        "." | "N" => tile,
+                        "W" => ParsedTile { wall: true, ..tile },
+                        "P" => ParsedTile { player: true, ..tile },
+                        "BB" => ParsedTile { block: Some(BoxColour::Blue), ..tile },
+                        "RB" => ParsedTile { block: Some(BoxColour::Red), ..tile },
+                        "BS" => ParsedTile { goal: Some(BoxColour::Blue), ..tile },
+                        "RS" => ParsedTile { goal: Some(BoxColour::Red), ..tile },
+                        _ => {
+                            return Err(LevelError::UnknownTile {
+                                row,
+                                column,
+                                tile: token.to_string(),
+                            })
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
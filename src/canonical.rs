@@ -0,0 +1,126 @@
+use crate::board::Board;
+
+// The level as trimmed standard text, so levels that only differ in the blank space around them
+// or in how they were written down compare equal.
+pub fn canonical(board: &Board) -> String {
+    let mut board = board.clone();
+    board.trim();
+    board.to_xsb()
+}
+
+// The smallest canonical text of the level rotated and mirrored in all eight ways.
+pub fn canonical_symmetric(board: &Board) -> String {
+    let mut variants = Vec::with_capacity(8);
+    let mut rotated = board.clone();
+    for _ in 0..4 {
+        variants.push(canonical(&rotated));
+        variants.push(canonical(&mirror(&rotated)));
+        rotated = rotate(&rotated);
+    }
+    variants.into_iter().min().unwrap_or_default()
+}
+
+// Stable across builds and platforms, unlike the standard library's hasher. Used to key save
+// data, so it must never change.
+pub fn level_hash(board: &Board) -> String {
+    fnv1a(&canonical(board))
+}
+
+pub fn symmetric_hash(board: &Board) -> String {
+    fnv1a(&canonical_symmetric(board))
+}
+
+fn fnv1a(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// a quarter turn clockwise
+fn rotate(board: &Board) -> Board {
+    transform(board, board.height, board.width, |x, y| (y, board.height - 1 - x))
+}
+
+// left to right
+fn mirror(board: &Board) -> Board {
+    transform(board, board.width, board.height, |x, y| (board.width - 1 - x, y))
+}
+
+// `source` maps a cell of the new board to the cell of `board` it comes from
+fn transform(
+    board: &Board,
+    width: usize,
+    height: usize,
+    source: impl Fn(usize, usize) -> (usize, usize),
+) -> Board {
+    let mut transformed = Board::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let (from_x, from_y) = source(x, y);
+            let (from, to) = (board.index(from_x, from_y), transformed.index(x, y));
+            transformed.cells[to] = board.cells[from];
+            transformed.goals[to] = board.goals[from];
+            transformed.boxes[to] = board.boxes[from];
+            if board.player == Some(from) {
+                transformed.player = Some(to);
+            }
+        }
+    }
+    transformed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BoxColour;
+
+    const LEVEL: &str = "
+#####
+#@$.#
+# $.#
+#####";
+
+    // changing either value breaks every saved progress file and replay
+    #[test]
+    fn hashes_are_pinned() {
+        let board = Board::parse(LEVEL).unwrap();
+        assert_eq!(level_hash(&board), "0387b28d5627c139");
+        assert_eq!(symmetric_hash(&board), "b38ca16aa9d568e3");
+    }
+
+    // the spot under a box of the other colour is part of the level
+    #[test]
+    fn a_spot_under_a_box_of_the_other_colour_changes_the_hash() {
+        let board = Board::parse(LEVEL).unwrap();
+        let mut covered = board.clone();
+        let index = covered.index(2, 1);
+        covered.goals[index] = Some(BoxColour::Red);
+
+        assert_ne!(level_hash(&covered), level_hash(&board));
+        assert_ne!(symmetric_hash(&covered), symmetric_hash(&board));
+        assert_eq!(Board::parse(&canonical(&covered)).unwrap(), covered);
+    }
+
+    #[test]
+    fn blank_space_around_the_level_does_not_change_the_hash() {
+        let board = Board::parse(LEVEL).unwrap();
+        let mut padded = board.clone();
+        padded.resize(board.width + 3, board.height + 2);
+        assert_eq!(level_hash(&padded), level_hash(&board));
+    }
+
+    #[test]
+    fn rotated_and_mirrored_levels_share_the_symmetric_hash() {
+        let board = Board::parse(LEVEL).unwrap();
+        let mut rotated = board.clone();
+        for _ in 0..4 {
+            assert_eq!(symmetric_hash(&rotated), symmetric_hash(&board));
+            assert_eq!(symmetric_hash(&mirror(&rotated)), symmetric_hash(&board));
+            rotated = rotate(&rotated);
+        }
+        assert_eq!(rotated, board);
+    }
+}
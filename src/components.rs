@@ -1,13 +1,13 @@
 use amethyst::ecs::{Component, DenseVecStorage, NullStorage};
 use amethyst::prelude::*;
-use std::fmt::Display;
-use std::fmt;
+
+pub use crate::board::BoxColour;
+pub use crate::rules::Direction;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Position {
     pub x: u8,
     pub y: u8,
-    pub z: u8,
 }
 
 impl Component for Position {
@@ -28,6 +28,15 @@ impl Component for Immovable {
     type Storage = NullStorage<Self>;
 }
 
+// The tileset sprite an entity is drawn with, kept so a theme change can swap it.
+pub struct Tile {
+    pub name: String,
+}
+
+impl Component for Tile {
+    type Storage = DenseVecStorage<Self>;
+}
+
 pub struct Wall {}
 
 impl Component for Wall {
@@ -40,22 +49,6 @@ impl Component for Player {
     type Storage = DenseVecStorage<Self>;
 }
 
-#[derive(PartialEq)]
-pub enum BoxColour {
-    Red,
-    Blue
-}
-
-impl Display for BoxColour {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.write_str(match self {
-            BoxColour::Red => "red",
-            BoxColour::Blue => "blue",
-        })?;
-        Ok(())
-    }
-}
-
 pub struct Box {
     pub colour: BoxColour,
 }
@@ -80,4 +73,5 @@ pub fn register_components(world: &mut World) {
     world.register::<Movable>();
     world.register::<Immovable>();
     world.register::<Position>();
+    world.register::<Tile>();
 }
@@ -1,107 +1,67 @@
 use crate::components::*;
+use amethyst::core::ecs::world::EntityBuilder;
 use amethyst::core::Transform;
 use amethyst::prelude::*;
-use amethyst::renderer::SpriteRender;
 
+use crate::assets::Theme;
 use crate::map::TILE_WIDTH;
-use crate::sokoban::MyPrefabData;
-use amethyst::assets::{Handle, Prefab};
 
-pub fn create_wall(world: &mut World, position: Position, sprite: SpriteRender) {
+fn create_tile<'a>(
+    world: &'a mut World,
+    position: Position,
+    z: f32,
+    tile: &str,
+    theme: &Theme,
+) -> EntityBuilder<'a> {
     let mut transform = Transform::default();
     transform.set_translation_xyz(
         position.x as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
         position.y as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        10.0,
+        z,
     );
 
-    world
+    let builder = world
         .create_entity()
         .with(transform)
-        .with(sprite.clone())
+        .with(theme.sprite(tile))
+        .with(Tile {
+            name: tile.to_string(),
+        })
+        .with(position);
+
+    match theme.prefab(tile) {
+        Some(prefab) => builder.with(prefab),
+        None => builder,
+    }
+}
+
+pub fn create_wall(world: &mut World, position: Position, tile: &str, theme: &Theme) {
+    create_tile(world, position, 10.0, tile, theme)
         .with(Wall {})
         .with(Immovable)
-        .with(position)
         .build();
 }
 
-pub fn create_box(world: &mut World, position: Position, sprite: SpriteRender, colour: BoxColour, box_prefab: &Handle<Prefab<MyPrefabData>>) {
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(
-        position.x as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        position.y as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        10.0,
-    );
-
-    world
-        .create_entity()
-        .with(transform)
-        .with(box_prefab.clone())
-        .with(sprite.clone())
+pub fn create_box(world: &mut World, position: Position, colour: BoxColour, theme: &Theme) {
+    create_tile(world, position, 10.0, &format!("box_{}", colour), theme)
         .with(Box { colour })
         .with(Movable)
-        .with(position)
         .build();
 }
 
-pub fn create_box_spot(
-    world: &mut World,
-    position: Position,
-    sprite: SpriteRender,
-    colour: BoxColour,
-) {
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(
-        position.x as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        position.y as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        9.0,
-    );
-
-    world
-        .create_entity()
-        .with(transform)
-        .with(sprite.clone())
+pub fn create_box_spot(world: &mut World, position: Position, colour: BoxColour, theme: &Theme) {
+    create_tile(world, position, 9.0, &format!("box_spot_{}", colour), theme)
         .with(BoxSpot { colour })
-        .with(position)
         .build();
 }
 
-pub fn create_floor(world: &mut World, position: Position, sprite: SpriteRender) {
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(
-        position.x as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        position.y as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        5.0,
-    );
-
-    world
-        .create_entity()
-        .with(transform)
-        .with(sprite.clone())
-        .with(position)
-        .build();
+pub fn create_floor(world: &mut World, position: Position, theme: &Theme) {
+    create_tile(world, position, 5.0, "floor", theme).build();
 }
 
-pub fn create_player(
-    world: &mut World,
-    position: Position,
-    sprite: SpriteRender,
-    player_prefab: &Handle<Prefab<MyPrefabData>>,
-) {
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(
-        position.x as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        position.y as f32 * TILE_WIDTH + 0.5 * TILE_WIDTH,
-        10.0,
-    );
-
-    world
-        .create_entity()
-        .with(player_prefab.clone())
-        .with(transform)
-        .with(sprite.clone())
+pub fn create_player(world: &mut World, position: Position, theme: &Theme) {
+    create_tile(world, position, 10.0, "player", theme)
         .with(Player {})
         .with(Movable)
-        .with(position)
         .build();
 }
@@ -1,19 +1,63 @@
-use amethyst::core::ecs::world::Index;
+use amethyst::core::ecs::Entity;
+
+use crate::components::{Direction, Position};
 
 #[derive(Debug)]
 pub enum MoveEvent {
     PlayerHitObstacle,
-    EntityMoved(EntityMoved),
+    PlayerMoved(PlayerMoved),
+    BoxPushed(BoxPushed),
     BoxPlacedOnSpot(BoxPlacedOnSpot),
+    BoxLeftSpot(BoxLeftSpot),
+    MoveUndone(MoveUndone),
+}
+
+// the game only reads the fields through `Debug`, in the debug overlay
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct PlayerMoved {
+    pub entity: Entity,
+    pub from: Position,
+    pub to: Position,
+    pub direction: Direction,
 }
 
+// like `PlayerMoved`, shown by the debug overlay
+#[allow(dead_code)]
 #[derive(Debug)]
-pub struct EntityMoved {
-    pub id: Index,
+pub struct BoxPushed {
+    pub entity: Entity,
+    pub from: Position,
+    pub to: Position,
 }
 
 #[derive(Debug)]
 pub struct BoxPlacedOnSpot {
-    pub is_correct_spot: bool,
+    // which box it was is only shown by the debug overlay
+    #[allow(dead_code)]
+    pub entity: Entity,
+    pub correct: bool,
 }
 
+#[derive(Debug)]
+pub struct BoxLeftSpot {
+    // only shown by the debug overlay
+    #[allow(dead_code)]
+    pub entity: Entity,
+}
+
+// the last step was taken back, everything it moved is where it was before
+#[derive(Debug)]
+pub struct MoveUndone {
+    pub pushed: bool,
+}
+
+// One-shot events about the level as a whole, written once when the game state changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    LevelStarted,
+    LevelWon,
+    LevelLost,
+    Paused,
+    Resumed,
+}
@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::analysis::analyse;
+use crate::board::{self, Board, BoxColour, Cell, COLOURS};
+use crate::rules::RuleSet;
+
+// positions the solver may look at to score an attempt, attempts it cannot solve are dropped
+const SOLVER_LIMIT: usize = 200_000;
+
+pub struct GeneratorSettings {
+    // outer size of the room, walls included
+    pub width: usize,
+    pub height: usize,
+    pub boxes: usize,
+    // 1 for blue boxes only, 2 to mix in red ones
+    pub colours: usize,
+    // the score to get close to, the highest score found when `None`
+    pub difficulty: Option<usize>,
+    pub attempts: usize,
+    // the rules the level is scored for
+    pub rules: RuleSet,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        GeneratorSettings {
+            width: 10,
+            height: 8,
+            boxes: 3,
+            colours: 1,
+            difficulty: None,
+            attempts: 50,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+// The measures of the best solution, see `analysis`.
+pub struct Generated {
+    pub board: Board,
+    pub moves: usize,
+    pub pushes: usize,
+    pub box_changes: usize,
+    pub difficulty: usize,
+}
+
+// Builds random rooms, puts the boxes on their spots and pulls them away again, which is the
+// reverse of pushing them, so every level it returns can be solved. Of all attempts the one whose
+// difficulty is closest to `settings.difficulty` wins. The difficulty is the one
+// `sokoban-analyse` reports, measured on the best solution the solver finds.
+//
+// The same seed and settings always give the same level.
+pub fn generate(settings: &GeneratorSettings, seed: u64) -> Option<Generated> {
+    board::check_size(settings.width, settings.height).ok()?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut best: Option<Generated> = None;
+
+    for _ in 0..settings.attempts {
+        let candidate = match attempt(settings, &mut rng) {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+
+        let better = match (&best, settings.difficulty) {
+            (None, _) => true,
+            (Some(best), Some(target)) => {
+                distance(candidate.difficulty, target) < distance(best.difficulty, target)
+            }
+            (Some(best), None) => candidate.difficulty > best.difficulty,
+        };
+        if better {
+            best = Some(candidate);
+        }
+    }
+
+    best
+}
+
+fn distance(score: usize, target: usize) -> usize {
+    (score as i64 - target as i64).unsigned_abs() as usize
+}
+
+fn attempt(settings: &GeneratorSettings, rng: &mut StdRng) -> Option<Generated> {
+    let mut board = room(settings.width, settings.height, rng);
+
+    let mut floor = (0..board.cells.len())
+        .filter(|&index| board.cells[index] == Cell::Floor)
+        .collect::<Vec<_>>();
+    if floor.len() < settings.boxes * 2 + 2 {
+        return None;
+    }
+    floor.shuffle(rng);
+
+    let colours = &COLOURS[..settings.colours.clamp(1, COLOURS.len())];
+    for (count, &index) in floor.iter().take(settings.boxes).enumerate() {
+        let colour = colours[count % colours.len()];
+        board.goals[index] = Some(colour);
+        board.boxes[index] = Some(colour);
+    }
+    board.player = Some(floor[settings.boxes]);
+
+    pull_boxes(&mut board, settings.boxes * 12, rng);
+    if board.boxes == board.goals {
+        return None;
+    }
+
+    board.fill_floor();
+    remove_hidden_walls(&mut board);
+    board.trim();
+
+    let analysis = analyse(&board, settings.rules, SOLVER_LIMIT);
+    Some(Generated {
+        moves: analysis.moves?,
+        pushes: analysis.pushes?,
+        box_changes: analysis.box_changes?,
+        difficulty: analysis.difficulty?,
+        board,
+    })
+}
+
+// A walled room carved out of overlapping rectangles, so all of its floor is connected.
+fn room(width: usize, height: usize, rng: &mut StdRng) -> Board {
+    let mut board = Board::new(width, height);
+    for cell in board.cells.iter_mut() {
+        *cell = Cell::Wall;
+    }
+    if width < 3 || height < 3 {
+        return board;
+    }
+
+    let rectangles = (width * height / 10).max(1);
+    let mut carved = 0;
+    for _ in 0..rectangles * 20 {
+        if carved == rectangles {
+            break;
+        }
+
+        let (w, h) = (rng.gen_range(1, 4), rng.gen_range(1, 4));
+        let x = rng.gen_range(1, width - 1);
+        let y = rng.gen_range(1, height - 1);
+        let cells = (y..(y + h).min(height - 1))
+            .flat_map(|y| (x..(x + w).min(width - 1)).map(move |x| (x, y)))
+            .map(|(x, y)| board.index(x, y))
+            .collect::<Vec<_>>();
+
+        let overlaps = cells.iter().any(|&index| board.cells[index] == Cell::Floor);
+        if carved == 0 || overlaps {
+            for index in cells {
+                board.cells[index] = Cell::Floor;
+            }
+            carved += 1;
+        }
+    }
+
+    board
+}
+
+// Pulls random boxes around and leaves the player where the last pull ended.
+fn pull_boxes(board: &mut Board, pulls: usize, rng: &mut StdRng) {
+    for _ in 0..pulls {
+        let player = board.player.expect("the generator places a player");
+        let reachable = walkable(board, player);
+
+        // (box, cell the player pulls from, cell the player ends up on)
+        let mut options = Vec::new();
+        for (index, block) in board.boxes.iter().enumerate() {
+            if block.is_none() {
+                continue;
+            }
+            let neighbours = board.neighbours(index);
+            for (direction, from) in neighbours.iter().enumerate() {
+                let from = match from {
+                    Some(from) if reachable[*from] => *from,
+                    _ => continue,
+                };
+                if let Some(to) = board.neighbours(from)[direction] {
+                    if is_free(board, to) {
+                        options.push((index, from, to));
+                    }
+                }
+            }
+        }
+
+        let &(index, from, to) = match options.choose(rng) {
+            Some(option) => option,
+            None => break,
+        };
+
+        board.boxes[from] = board.boxes[index].take();
+        board.player = Some(to);
+    }
+}
+
+fn is_free(board: &Board, index: usize) -> bool {
+    board.cells[index] == Cell::Floor && board.boxes[index].is_none()
+}
+
+// the cells the player can walk to without moving a box
+fn walkable(board: &Board, start: usize) -> Vec<bool> {
+    let mut seen = vec![false; board.cells.len()];
+    let mut queue = VecDeque::new();
+    seen[start] = true;
+    queue.push_back(start);
+
+    while let Some(index) = queue.pop_front() {
+        for &next in board.neighbours(index).iter().flatten() {
+            if !seen[next] && is_free(board, next) {
+                seen[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    seen
+}
+
+// walls that do not touch any floor, even diagonally, are not drawn
+fn remove_hidden_walls(board: &mut Board) {
+    let hidden = (0..board.cells.len())
+        .filter(|&index| board.cells[index] == Cell::Wall)
+        .filter(|&index| {
+            let (x, y) = board.position(index);
+            let x_range = x.saturating_sub(1)..=(x + 1).min(board.width - 1);
+            !x_range.into_iter().any(|nx| {
+                let y_range = y.saturating_sub(1)..=(y + 1).min(board.height - 1);
+                y_range
+                    .into_iter()
+                    .any(|ny| board.cells[board.index(nx, ny)] == Cell::Floor)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for index in hidden {
+        board.cells[index] = Cell::Outside;
+    }
+}
+
+// a one line summary, used as the title of generated levels
+pub fn describe(generated: &Generated) -> String {
+    let count = |colour: BoxColour| {
+        generated
+            .board
+            .boxes
+            .iter()
+            .filter(|&&block| block == Some(colour))
+            .count()
+    };
+    let boxes = COLOURS
+        .iter()
+        .filter(|&&colour| count(colour) > 0)
+        .map(|&colour| format!("{} {}", count(colour), colour))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "{}x{}, boxes: {}, moves: {}, pushes: {}, box changes: {}, difficulty: {}",
+        generated.board.width,
+        generated.board.height,
+        boxes,
+        generated.moves,
+        generated.pushes,
+        generated.box_changes,
+        generated.difficulty
+    )
+}
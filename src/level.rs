@@ -0,0 +1,204 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::board::{Board, LevelError};
+use crate::{slc, tiled};
+
+pub struct LevelInfo {
+    // where the level is in its file, counting from 1 and including levels that were skipped
+    pub number: usize,
+    pub title: String,
+    pub copyright: Option<String>,
+    pub board: Board,
+}
+
+// `name` is the file name, the rest is only known for imported collections.
+#[derive(Default)]
+pub struct LevelPack {
+    pub name: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub copyright: Option<String>,
+    pub description: Option<String>,
+    pub levels: Vec<LevelInfo>,
+    pub skipped: Vec<SkippedLevel>,
+}
+
+// A level of the file that cannot be played and is left out of `levels`.
+pub struct SkippedLevel {
+    pub number: usize,
+    pub title: String,
+    pub error: LevelError,
+}
+
+impl fmt::Display for SkippedLevel {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "level {} '{}' cannot be played: {}", self.number, self.title, self.error)
+    }
+}
+
+impl LevelPack {
+    pub fn load(path: &Path) -> io::Result<LevelPack> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("levels")
+            .to_string();
+        let contents = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("slc") | Some("xml") => slc::parse(name, &contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Some("tmx") => tiled::load(name, path, &contents)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            _ => Ok(LevelPack::parse(name, &contents)),
+        }
+    }
+
+    pub fn parse(name: String, contents: &str) -> LevelPack {
+        let mut pack = LevelPack {
+            name,
+            ..Default::default()
+        };
+        for (title, map) in split_levels(contents) {
+            pack.add_level(title, None, &map);
+        }
+        pack
+    }
+
+    // Levels that cannot be played are left out of the pack.
+    pub fn add_level(&mut self, title: Option<String>, copyright: Option<String>, map: &str) {
+        match Board::parse(map) {
+            Ok(board) => self.add_board(title, copyright, board),
+            Err(e) => self.skip(title, e),
+        }
+    }
+
+    pub fn add_board(&mut self, title: Option<String>, copyright: Option<String>, board: Board) {
+        match board.errors().into_iter().next() {
+            Some(error) => self.skip(title, error),
+            None => self.levels.push(LevelInfo {
+                number: self.next_number(),
+                title: title.unwrap_or_else(|| format!("Level {}", self.next_number())),
+                copyright,
+                board,
+            }),
+        }
+    }
+
+    fn skip(&mut self, title: Option<String>, error: LevelError) {
+        let skipped = SkippedLevel {
+            number: self.next_number(),
+            title: title.unwrap_or_else(|| format!("Level {}", self.next_number())),
+            error,
+        };
+        log::error!("skipping level in pack '{}': {}", self.name, skipped);
+        self.skipped.push(skipped);
+    }
+
+    fn next_number(&self) -> usize {
+        self.levels.len() + self.skipped.len() + 1
+    }
+
+    // The level at `number` in the file, or why it cannot be played.
+    pub fn level(&self, number: usize) -> Result<&LevelInfo, String> {
+        if let Some(level) = self.levels.iter().find(|level| level.number == number) {
+            return Ok(level);
+        }
+        match self.skipped.iter().find(|skipped| skipped.number == number) {
+            Some(skipped) => Err(skipped.to_string()),
+            None => Err(format!("{} has no level {}", self.name, number)),
+        }
+    }
+
+    // For the command line tools, which have no logger to show the skipped levels.
+    pub fn print_skipped(&self) {
+        for skipped in &self.skipped {
+            eprintln!("{}: {}", self.name, skipped);
+        }
+    }
+
+    // the collection title when there is one
+    pub fn display_name(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.name)
+    }
+}
+
+// Levels are separated by blank lines, `;` lines give the title of the level below.
+pub fn split_levels(contents: &str) -> Vec<(Option<String>, String)> {
+    let mut levels = Vec::new();
+    let mut title = None;
+    let mut rows: Vec<&str> = Vec::new();
+
+    for line in contents.lines().chain(std::iter::once("")) {
+        // leading blanks are outside cells in the standard format
+        let line = line.trim_end();
+
+        if let Some(comment) = line.trim_start().strip_prefix(';') {
+            title = Some(comment.trim().to_string());
+        } else if !line.trim_start().is_empty() {
+            rows.push(line);
+        } else if !rows.is_empty() {
+            levels.push((title.take(), rows.join("\n")));
+            rows.clear();
+        }
+    }
+
+    levels
+}
+
+#[derive(Default)]
+pub struct LevelPacks {
+    pub packs: Vec<LevelPack>,
+}
+
+impl LevelPacks {
+    pub fn load(dir: &Path) -> io::Result<LevelPacks> {
+        let mut paths = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ["txt", "slc", "xml", "tmx"].iter().any(|&known| ext == known))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        // like a broken level inside a pack, a broken pack file is left out
+        let packs = paths
+            .iter()
+            .filter_map(|path| match LevelPack::load(path) {
+                Ok(pack) => Some(pack),
+                Err(e) => {
+                    log::error!("skipping level pack {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect();
+
+        Ok(LevelPacks { packs })
+    }
+
+    pub fn get(&self, level: LevelRef) -> Option<&LevelInfo> {
+        self.packs
+            .get(level.pack)
+            .and_then(|pack| pack.levels.get(level.index))
+    }
+
+    pub fn next(&self, level: LevelRef) -> Option<LevelRef> {
+        self.iter().skip_while(|&other| other != level).nth(1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = LevelRef> + '_ {
+        self.packs.iter().enumerate().flat_map(|(pack, levels)| {
+            (0..levels.levels.len()).map(move |index| LevelRef { pack, index })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelRef {
+    pub pack: usize,
+    pub index: usize,
+}
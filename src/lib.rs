@@ -0,0 +1,15 @@
+// The parts of the game that do not need a window, shared by the game and the command line tools.
+pub mod analysis;
+pub mod animation;
+pub mod asset_files;
+pub mod board;
+pub mod canonical;
+pub mod generator;
+pub mod level;
+pub mod render;
+pub mod replay;
+pub mod rules;
+pub mod slc;
+pub mod solutions;
+pub mod solver;
+pub mod tiled;
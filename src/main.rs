@@ -14,22 +14,46 @@ use amethyst::{
     utils::application_root_dir,
 };
 
-use crate::sokoban::{AnimationId, MyPrefabData, Sokoban};
-use amethyst::audio::AudioBundle;
+use crate::level::LevelPacks;
+use sokoban_tutorial::{asset_files, board, canonical, generator, level, replay, rules};
+use crate::progress::Progress;
+use crate::sokoban::{AnimationId, CurrentState, MyPrefabData};
+use crate::states::LoadingState;
+use crate::music::{Music, Playlists};
+use amethyst::audio::{AudioBundle, DjSystemDesc};
 use amethyst::utils::fps_counter::FpsCounterBundle;
+use amethyst::window::DisplayConfig;
+use crate::settings::SettingsFiles;
+use crate::theme::ThemedWindow;
 
+mod assets;
+mod autotile;
 mod components;
 mod entities;
 mod map;
+mod music;
+mod progress;
+mod settings;
 mod sokoban;
+mod sounds;
+mod states;
 mod systems;
+mod theme;
+mod tile_grid;
 mod events;
 
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
 
     let app_root = application_root_dir()?;
-    let display_config_path = app_root.join("config").join("display.ron");
+    let settings_files = SettingsFiles {
+        defaults: app_root.join("config").join("settings.ron"),
+        user: app_root.join("user").join("settings.ron"),
+    };
+    let settings = settings_files.load()?;
+
+    let mut display_config = DisplayConfig::load(app_root.join("config").join("display.ron"))?;
+    display_config.dimensions = Some(settings.display.resolution);
 
     let binding_path = app_root.join("config").join("bindings.ron");
     let input_bundle =
@@ -47,10 +71,10 @@ fn main() -> amethyst::Result<()> {
         ))?
         .with_bundle(
             RenderingBundle::<DefaultBackend>::new()
-                .with_plugin(
-                    RenderToWindow::from_config_path(display_config_path)?
-                        .with_clear([0.00196, 0.23726, 0.21765, 1.0]),
-                )
+                .with_plugin(ThemedWindow::new(
+                    RenderToWindow::from_config(display_config),
+                    [0.00196, 0.23726, 0.21765, 1.0],
+                ))
                 .with_plugin(RenderFlat2D::default())
                 .with_plugin(RenderUi::default()),
         )?
@@ -61,22 +85,64 @@ fn main() -> amethyst::Result<()> {
         .with_bundle(input_bundle)?
         .with_bundle(UiBundle::<StringBindings>::new())?
         .with_bundle(AudioBundle::default())?
+        .with_system_desc(
+            DjSystemDesc::new(|music: &mut Music| music.next_track()),
+            "dj_system",
+            &[],
+        )
+        .with(systems::MusicSystem::default(), "music_system", &["dj_system"])
         .with_bundle(FpsCounterBundle)?
         .with(
-            systems::MovementSystem { input_reader: None },
+            systems::MovementSystem::default(),
             "movement_system",
             &["input_system"],
         )
         .with(
-            systems::GameplayStateSystem {},
+            systems::GameplayStateSystem::default().pausable(CurrentState::Level),
             "gameplay_state_system",
-            &[],
+            &["movement_system"],
         )
         .with(systems::AnimationSystem {}, "animation_system", &[])
-        .with(systems::SoundSystem { move_reader: None }, "sound_system", &[]);
+        .with(
+            systems::SoundSystem::default().pausable(CurrentState::Level),
+            "sound_system",
+            &["gameplay_state_system"],
+        )
+        .with(
+            systems::StatsSystem::default().pausable(CurrentState::Level),
+            "stats_system",
+            &["movement_system"],
+        )
+        .with(
+            systems::GameUiSystem::default().pausable(CurrentState::Level),
+            "game_ui_system",
+            &["gameplay_state_system", "stats_system"],
+        )
+        .with(
+            systems::ProgressSystem::default().pausable(CurrentState::Level),
+            "progress_system",
+            &["gameplay_state_system"],
+        )
+        .with(
+            systems::DebugOverlaySystem::default(),
+            "debug_overlay_system",
+            &["movement_system"],
+        );
 
     let assets_dir = app_root.join("assets");
-    let mut game = Application::new(assets_dir, Sokoban, game_data)?;
+    let level_packs = LevelPacks::load(&assets_dir.join("levels"))?;
+    let mut progress = Progress::load(app_root.join("user").join("progress.ron"));
+    progress.migrate(&level_packs);
+    let playlists = Playlists::load(assets_dir.join("music").join("playlists.ron"))?;
+
+    let mut game = Application::build(assets_dir, LoadingState::default())?
+        .with_resource(level_packs)
+        .with_resource(progress)
+        .with_resource(settings)
+        .with_resource(settings_files)
+        .with_resource(playlists)
+        .with_resource(Music::default())
+        .build(game_data)?;
     game.run();
 
     Ok(())
@@ -1,101 +1,63 @@
-use amethyst::assets::{PrefabLoader, RonFormat};
-use amethyst::prelude::World;
+use amethyst::ecs::{Entities, ReadStorage};
+use amethyst::prelude::*;
 
-use crate::components::{BoxColour, Position};
+use crate::assets::current_theme;
+use crate::autotile::wall_sprite;
+use crate::board::{Board, Cell};
+use crate::components::Position;
 use crate::entities::*;
-use crate::sokoban::{ImageAssets, MyPrefabData};
+use crate::tile_grid::TileGrid;
 
 pub const TILE_WIDTH: f32 = 32.0;
-pub const MAP_WIDTH: u8 = 8;
-pub const MAP_HEIGHT: u8 = 9;
 
-pub fn load_map(world: &mut World, map_string: String, assets: &ImageAssets) {
-    // rev() because y coordinates are switched!
-    let rows: Vec<&str> = map_string
-        .trim()
-        .split('\n')
-        .map(|x| x.trim())
-        .rev()
-        .collect();
-
-    let player_prefab = world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-        loader.load("prefab/player_animation.ron", RonFormat, ())
-    });
+#[derive(Default)]
+pub struct MapSize {
+    pub width: u8,
+    pub height: u8,
+}
 
-    let box_red_prefab = world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-        loader.load("prefab/box_red_animation.ron", RonFormat, ())
-    });
+// The board counts rows from the top, positions from the bottom.
+pub fn board_position(board: &Board, index: usize) -> Position {
+    let (x, row) = board.position(index);
+    Position {
+        x: x as u8,
+        y: (board.height - 1 - row) as u8,
+    }
+}
 
-    let box_blue_prefab = world.exec(|loader: PrefabLoader<'_, MyPrefabData>| {
-        loader.load("prefab/box_blue_animation.ron", RonFormat, ())
-    });
+pub fn load_map(world: &mut World, board: &Board) {
+    let (width, height) = (board.width as u8, board.height as u8);
+    world.insert(MapSize { width, height });
+    // the movement rules play on the board, the entities follow it
+    world.insert(board.clone());
 
-    for (y, row) in rows.iter().enumerate() {
-        let columns: Vec<&str> = row.split(' ').collect();
+    let theme = current_theme(world);
 
-        for (x, column) in columns.iter().enumerate() {
-            let position = Position {
-                x: x as u8,
-                y: y as u8,
-                z: 0,
-            };
+    for index in 0..board.cells.len() {
+        let position = board_position(board, index);
 
-            match *column {
-                "." => create_floor(world, position, assets.floor_sprite.clone()),
-                "W" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_wall(world, position, assets.wall_sprite.clone());
-                }
-                "P" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_player(
-                        world,
-                        position,
-                        assets.player_sprite.clone(),
-                        &player_prefab,
-                    );
-                }
-                "BB" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_box(
-                        world,
-                        position,
-                        assets.box_blue_sprite.clone(),
-                        BoxColour::Blue,
-                        &box_blue_prefab
-                    );
-                }
-                "RB" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_box(
-                        world,
-                        position,
-                        assets.box_red_sprite.clone(),
-                        BoxColour::Red,
-                        &box_red_prefab
-                    );
-                }
-                "BS" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_box_spot(
-                        world,
-                        position,
-                        assets.box_spot_blue_sprite.clone(),
-                        BoxColour::Blue,
-                    );
-                }
-                "RS" => {
-                    create_floor(world, position, assets.floor_sprite.clone());
-                    create_box_spot(
-                        world,
-                        position,
-                        assets.box_spot_red_sprite.clone(),
-                        BoxColour::Red,
-                    );
-                }
-                "N" => (),
-                c => panic!("unrecognized map item! {}", c),
+        match board.cells[index] {
+            Cell::Outside => continue,
+            Cell::Wall => {
+                create_wall(world, position, &wall_sprite(&theme, board, index), &theme);
+                continue;
             }
+            Cell::Floor => create_floor(world, position, &theme),
+        }
+
+        if let Some(colour) = board.goals[index] {
+            create_box_spot(world, position, colour, &theme);
+        }
+        if let Some(colour) = board.boxes[index] {
+            create_box(world, position, colour, &theme);
+        }
+        if board.player == Some(index) {
+            create_player(world, position, &theme);
         }
     }
+
+    let grid = world.exec(|(entities, positions): (Entities, ReadStorage<Position>)| {
+        TileGrid::from_positions(width, height, &entities, &positions)
+    });
+    world.insert(grid);
 }
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use amethyst::assets::Loader;
+use amethyst::audio::{OggFormat, SourceHandle, WavFormat};
+use amethyst::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct Playlists {
+    pub menu: Vec<String>,
+    pub levels: Vec<String>,
+    #[serde(default)]
+    pub packs: HashMap<String, Vec<String>>,
+}
+
+impl Playlists {
+    pub fn for_pack(&self, pack: &str) -> &[String] {
+        self.packs.get(pack).unwrap_or(&self.levels)
+    }
+}
+
+// The tracks `DjSystem` cycles through. `MusicSystem` crossfades whenever the playlist changes.
+#[derive(Default)]
+pub struct Music {
+    playlist: Vec<String>,
+    tracks: Vec<SourceHandle>,
+    next: usize,
+    pub(crate) changed: bool,
+}
+
+impl Music {
+    pub fn next_track(&mut self) -> Option<SourceHandle> {
+        let track = self.tracks.get(self.next).cloned();
+        self.next = (self.next + 1) % self.tracks.len().max(1);
+        track
+    }
+}
+
+pub fn play_menu_music(world: &mut World) {
+    let playlist = world.read_resource::<Playlists>().menu.clone();
+    play_playlist(world, playlist);
+}
+
+pub fn play_pack_music(world: &mut World, pack: &str) {
+    let playlist = world.read_resource::<Playlists>().for_pack(pack).to_vec();
+    play_playlist(world, playlist);
+}
+
+fn play_playlist(world: &mut World, playlist: Vec<String>) {
+    if world.read_resource::<Music>().playlist == playlist {
+        return;
+    }
+
+    let tracks = playlist
+        .iter()
+        .map(|track| load_track(world, track))
+        .collect();
+
+    *world.write_resource::<Music>() = Music {
+        playlist,
+        tracks,
+        next: 0,
+        changed: true,
+    };
+}
+
+fn load_track(world: &World, path: &str) -> SourceHandle {
+    let loader = world.read_resource::<Loader>();
+    let storage = world.read_resource();
+
+    if path.ends_with(".ogg") {
+        loader.load(path, OggFormat, (), &storage)
+    } else {
+        loader.load(path, WavFormat, (), &storage)
+    }
+}
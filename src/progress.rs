@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use amethyst::config::{Config, ConfigError};
+use serde::{Deserialize, Serialize};
+
+use crate::canonical::level_hash;
+use crate::level::{LevelPacks, LevelRef};
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub solved: BTreeSet<String>,
+    pub last_played: Option<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Progress {
+    pub fn load(path: PathBuf) -> Progress {
+        let progress = if path.exists() {
+            Progress::load_from(&path)
+        } else {
+            Progress::default()
+        };
+
+        Progress { path, ..progress }
+    }
+
+    fn load_from(path: &Path) -> Progress {
+        match <Progress as Config>::load(path) {
+            Ok(progress) => progress,
+            Err(e) => {
+                log::error!("could not read {}: {}", path.display(), e);
+                Progress::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        self.write(&self.path)
+    }
+
+    pub fn is_solved(&self, packs: &LevelPacks, level: LevelRef) -> bool {
+        self.solved.contains(&level_key(packs, level))
+    }
+
+    pub fn mark_solved(&mut self, packs: &LevelPacks, level: LevelRef) {
+        self.solved.insert(level_key(packs, level));
+    }
+
+    pub fn mark_played(&mut self, packs: &LevelPacks, level: LevelRef) {
+        self.last_played = Some(level_key(packs, level));
+    }
+
+    // Older saves keyed levels by pack name and position, which broke when a pack changed.
+    pub fn migrate(&mut self, packs: &LevelPacks) {
+        let old_keys = packs
+            .iter()
+            .map(|level| {
+                let pack = &packs.packs[level.pack].name;
+                (format!("{}/{}", pack, level.index + 1), level_key(packs, level))
+            })
+            .collect::<Vec<_>>();
+
+        for (old, new) in old_keys {
+            if self.solved.remove(&old) {
+                self.solved.insert(new.clone());
+            }
+            if self.last_played.as_ref() == Some(&old) {
+                self.last_played = Some(new);
+            }
+        }
+    }
+
+    // the last played level if it is still unsolved, otherwise the next unsolved one after it
+    pub fn continue_level(&self, packs: &LevelPacks) -> Option<LevelRef> {
+        let levels = packs.iter().collect::<Vec<_>>();
+        let start = levels
+            .iter()
+            .position(|&level| Some(level_key(packs, level)) == self.last_played)
+            .unwrap_or(0);
+
+        levels
+            .iter()
+            .cycle()
+            .skip(start)
+            .take(levels.len())
+            .copied()
+            .find(|&level| !self.is_solved(packs, level))
+    }
+}
+
+// the canonical hash, so progress follows a level when packs are edited or reordered
+pub fn level_key(packs: &LevelPacks, level: LevelRef) -> String {
+    packs
+        .get(level)
+        .map(|info| level_hash(&info.board))
+        .unwrap_or_default()
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+
+use crate::asset_files::{
+    load_theme, read_ron, wall_tile, AssetManifest, ThemeDefinition, TilesetDefinition,
+};
+use crate::board::{Board, Cell};
+
+// The tiles of a theme cut out of its texture, to draw boards without starting the engine.
+pub struct TileImages {
+    pub tile_size: (u32, u32),
+    pub background: Rgba<u8>,
+    tiles: HashMap<String, RgbaImage>,
+}
+
+impl TileImages {
+    pub fn load(
+        assets_dir: &Path,
+        theme: &ThemeDefinition,
+        tileset: &TilesetDefinition,
+    ) -> Result<TileImages, String> {
+        tileset.check()?;
+        let path = assets_dir.join(&tileset.texture);
+        let texture = image::open(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .to_rgba8();
+
+        let (width, height) = tileset.tile_size;
+        let mut tiles = HashMap::new();
+        for (name, &index) in tileset.sprites.iter() {
+            let (column, row) = (index as u32 % tileset.columns, index as u32 / tileset.columns);
+            if (column + 1) * width > texture.width() || (row + 1) * height > texture.height() {
+                return Err(format!(
+                    "{}: sprite \"{}\" lies outside the texture",
+                    path.display(),
+                    name
+                ));
+            }
+            let tile = imageops::crop_imm(&texture, column * width, row * height, width, height);
+            tiles.insert(name.clone(), tile.to_image());
+        }
+
+        Ok(TileImages {
+            tile_size: tileset.tile_size,
+            background: colour(theme.background),
+            tiles,
+        })
+    }
+
+    fn draw(&self, image: &mut RgbaImage, name: &str, x: u32, y: u32) {
+        if let Some(tile) = self.tiles.get(name) {
+            imageops::overlay(image, tile, x, y);
+        }
+    }
+}
+
+// The theme in the manifest whose name or file name matches `theme`, ignoring case, or the first.
+pub fn find_theme(
+    assets_dir: &Path,
+    theme: Option<&str>,
+) -> Result<(ThemeDefinition, TilesetDefinition), String> {
+    let manifest: AssetManifest = read_ron(&assets_dir.join("manifest.ron"))?;
+    for path in &manifest.themes {
+        let (definition, tileset) = load_theme(assets_dir, path)?;
+        let stem = Path::new(path).file_stem().and_then(|stem| stem.to_str());
+        let matches = theme.is_none_or(|theme| {
+            definition.name.eq_ignore_ascii_case(theme)
+                || stem.is_some_and(|stem| stem.eq_ignore_ascii_case(theme))
+        });
+        if matches {
+            return Ok((definition, tileset));
+        }
+    }
+
+    Err(match theme {
+        Some(theme) => format!("no theme called \"{}\" in the manifest", theme),
+        None => "the asset manifest lists no themes".to_string(),
+    })
+}
+
+// theme colours are linear, the window shows them in sRGB
+pub fn colour(linear: [f32; 4]) -> Rgba<u8> {
+    let [r, g, b, a] = linear;
+    Rgba([srgb(r), srgb(g), srgb(b), (a.clamp(0., 1.) * 255.).round() as u8])
+}
+
+fn srgb(linear: f32) -> u8 {
+    let value = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    };
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+// Draws the board the way the game lays it out: floor under everything but walls, then spots,
+// boxes and the player. Cells outside the level get the theme background, or stay clear when
+// `transparent` is set.
+pub fn render(board: &Board, tiles: &TileImages, transparent: bool) -> RgbaImage {
+    let (tile_width, tile_height) = tiles.tile_size;
+    let background = if transparent {
+        Rgba([0, 0, 0, 0])
+    } else {
+        tiles.background
+    };
+    let mut image = RgbaImage::from_pixel(
+        board.width as u32 * tile_width,
+        board.height as u32 * tile_height,
+        background,
+    );
+
+    for index in 0..board.cells.len() {
+        let (column, row) = board.position(index);
+        let (x, y) = (column as u32 * tile_width, row as u32 * tile_height);
+
+        match board.cells[index] {
+            Cell::Outside => continue,
+            Cell::Wall => {
+                let name = wall_tile(board.wall_mask(index), |name| tiles.tiles.contains_key(name));
+                tiles.draw(&mut image, &name, x, y);
+                continue;
+            }
+            Cell::Floor => tiles.draw(&mut image, "floor", x, y),
+        }
+
+        if let Some(colour) = board.goals[index] {
+            tiles.draw(&mut image, &format!("box_spot_{}", colour), x, y);
+        }
+        if let Some(colour) = board.boxes[index] {
+            tiles.draw(&mut image, &format!("box_{}", colour), x, y);
+        }
+        if board.player == Some(index) {
+            tiles.draw(&mut image, "player", x, y);
+        }
+    }
+
+    image
+}
+
+// Pixel art stays sharp when enlarged, thumbnails are smoothed.
+pub fn scale(image: &RgbaImage, factor: f32) -> RgbaImage {
+    let width = ((image.width() as f32 * factor).round() as u32).max(1);
+    let height = ((image.height() as f32 * factor).round() as u32).max(1);
+    let filter = if factor >= 1. {
+        FilterType::Nearest
+    } else {
+        FilterType::Triangle
+    };
+    imageops::resize(image, width, height, filter)
+}
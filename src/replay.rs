@@ -0,0 +1,311 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::canonical::level_hash;
+use crate::rules::{Direction, Game, Illegal, RuleSet, Stats};
+
+// The replay format written by this version. Version 0 is a bare move string in LURD notation,
+// which still loads as a replay without any metadata.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Action {
+    // a move in LURD notation, uppercase when it pushed
+    Move(char),
+    Undo,
+    Redo,
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Event {
+    // milliseconds since the recording started
+    pub at_ms: u64,
+    pub action: Action,
+}
+
+// One attempt at a level, saved as RON or JSON. The version, the level and the result are
+// required in those, the rest is metadata with a default. Fields added after version 1 need a
+// default as well so older replays keep loading.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Replay {
+    pub version: u32,
+    // the level hash of the level played, empty in version 0 replays
+    pub level: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub rules: RuleSet,
+    #[serde(default)]
+    pub game_version: String,
+    // the name of the player profile that recorded it
+    #[serde(default)]
+    pub player: String,
+    // seconds since the unix epoch
+    #[serde(default)]
+    pub started: u64,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    // the counts and result at the end of the recording
+    pub stats: Stats,
+    pub solved: bool,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Read { file: String, message: String },
+    Format(String),
+    NewerVersion(u32),
+    NoLevel,
+    WrongLevel { expected: String, found: String },
+    // `event` counts from 1
+    IllegalMove { event: usize, letter: char, reason: Illegal },
+    Mismatch { recorded: (Stats, bool), replayed: (Stats, bool) },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = |(stats, solved): &(Stats, bool)| {
+            format!(
+                "{} moves, {} pushes, {}",
+                stats.moves,
+                stats.pushes,
+                if *solved { "solved" } else { "not solved" }
+            )
+        };
+        match self {
+            ReplayError::Read { file, message } => write!(fmt, "could not read {}: {}", file, message),
+            ReplayError::Format(message) => write!(fmt, "not a replay: {}", message),
+            ReplayError::NewerVersion(version) => write!(
+                fmt,
+                "replay version {} is newer than the supported version {}",
+                version, VERSION
+            ),
+            ReplayError::NoLevel => write!(fmt, "the replay does not say which level it belongs to"),
+            ReplayError::WrongLevel { expected, found } => write!(
+                fmt,
+                "the replay was recorded on level {}, this is level {}",
+                expected, found
+            ),
+            ReplayError::IllegalMove { event, letter, reason } => {
+                write!(fmt, "event {} ({}): {}", event, letter, reason)
+            }
+            ReplayError::Mismatch { recorded, replayed } => write!(
+                fmt,
+                "the replay ends with {} but playing it gives {}",
+                outcome(recorded),
+                outcome(replayed)
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl Replay {
+    // A version 0 replay: the moves without timings or any other metadata.
+    pub fn from_moves(moves: &str) -> Replay {
+        let events = moves
+            .chars()
+            .filter(|letter| !letter.is_whitespace())
+            .map(|letter| Event {
+                at_ms: 0,
+                action: Action::Move(letter),
+            })
+            .collect();
+        Replay {
+            events,
+            ..Default::default()
+        }
+    }
+
+    // JSON, RON or a bare move string, told apart by the first character.
+    pub fn parse(contents: &str) -> Result<Replay, ReplayError> {
+        let text = contents.trim_start();
+        let replay: Replay = match text.chars().next() {
+            Some('{') => serde_json::from_str(text).map_err(|e| ReplayError::Format(e.to_string()))?,
+            Some('(') | Some('#') => {
+                ron::de::from_str(text).map_err(|e| ReplayError::Format(e.to_string()))?
+            }
+            _ if text.chars().all(|c| c.is_whitespace() || Direction::from_char(c).is_some()) => {
+                return Ok(Replay::from_moves(text));
+            }
+            _ => return Err(ReplayError::Format("expected RON, JSON or LURD moves".to_string())),
+        };
+
+        // version 0 is only ever a move string, so a file cannot skip the checks by claiming it
+        if replay.version == 0 {
+            return Err(ReplayError::Format("version 0 replays are bare move strings".to_string()));
+        }
+        if replay.version > VERSION {
+            return Err(ReplayError::NewerVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        let contents = fs::read_to_string(path).map_err(|e| ReplayError::Read {
+            file: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Replay::parse(&contents)
+    }
+
+    // JSON for `.json` files, RON otherwise
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = if path.extension().is_some_and(|extension| extension == "json") {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        } else {
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())?
+        };
+        fs::write(path, contents).map_err(|e| format!("could not write {}: {}", path.display(), e))
+    }
+
+    // The newest replay in `dir` of this level under these rules, going by the file names
+    // `sokoban-tui --record` gives them. Files that do not load are left out.
+    pub fn latest(dir: &Path, board: &Board, rules: RuleSet) -> Option<Replay> {
+        let prefix = format!("{}-", level_hash(board));
+        fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .filter_map(|entry| Replay::load(&entry.path()).ok())
+            .filter(|replay| replay.rules == rules && replay.check_level(board).is_ok())
+            .max_by_key(|replay| replay.started)
+    }
+
+    // The moves left standing at the end, with undos, redos and restarts applied.
+    pub fn moves(&self) -> String {
+        let mut moves = String::new();
+        let mut undone = Vec::new();
+        for event in &self.events {
+            match event.action {
+                Action::Move(letter) => {
+                    moves.push(letter);
+                    undone.clear();
+                }
+                Action::Undo => undone.extend(moves.pop()),
+                Action::Redo => moves.extend(undone.pop()),
+                Action::Restart => {
+                    moves.clear();
+                    undone.clear();
+                }
+            }
+        }
+        moves
+    }
+
+    // Version 0 replays have no level hash and fit any level.
+    pub fn check_level(&self, board: &Board) -> Result<(), ReplayError> {
+        if self.version == 0 {
+            return Ok(());
+        }
+        if self.level.is_empty() {
+            return Err(ReplayError::NoLevel);
+        }
+
+        let found = level_hash(board);
+        if self.level == found {
+            Ok(())
+        } else {
+            Err(ReplayError::WrongLevel {
+                expected: self.level.clone(),
+                found,
+            })
+        }
+    }
+
+    // Plays the events on a game, stopping at the first move the game's rules do not allow.
+    pub fn play(&self, game: &mut Game) -> Result<(), ReplayError> {
+        for (index, event) in self.events.iter().enumerate() {
+            match event.action {
+                Action::Move(letter) => {
+                    let illegal = |reason| ReplayError::IllegalMove {
+                        event: index + 1,
+                        letter,
+                        reason,
+                    };
+                    let direction = Direction::from_char(letter).ok_or_else(|| illegal(Illegal::NotAMove))?;
+                    game.step(direction).map_err(illegal)?;
+                }
+                Action::Undo => {
+                    game.undo();
+                }
+                Action::Redo => {
+                    game.redo();
+                }
+                Action::Restart => game.restart(),
+            }
+        }
+        Ok(())
+    }
+
+    // Version 0 replays have no recorded result to compare with.
+    pub fn check_outcome(&self, game: &Game) -> Result<(), ReplayError> {
+        let recorded = (self.stats, self.solved);
+        let replayed = (game.stats, game.is_solved());
+        if self.version == 0 || recorded == replayed {
+            Ok(())
+        } else {
+            Err(ReplayError::Mismatch { recorded, replayed })
+        }
+    }
+
+    // Checks the replay belongs to the level of a game that has not started yet, plays it and
+    // compares the result with the recorded one. The game is left where the replay stopped, also
+    // after an illegal move.
+    pub fn validate(&self, game: &mut Game) -> Result<(), ReplayError> {
+        self.check_level(&game.board)?;
+        self.play(game)?;
+        self.check_outcome(game)
+    }
+}
+
+// Builds a replay while a level is played. Only record what changed the game: moves that were
+// made and undos or redos that had something to take back.
+pub struct Recorder {
+    pub replay: Replay,
+    clock: Instant,
+}
+
+impl Recorder {
+    pub fn new(board: &Board, title: &str, rules: RuleSet, player: &str) -> Recorder {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let replay = Replay {
+            version: VERSION,
+            level: level_hash(board),
+            title: title.to_string(),
+            rules,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            player: player.to_string(),
+            started,
+            ..Default::default()
+        };
+        Recorder {
+            replay,
+            clock: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, action: Action) {
+        self.replay.events.push(Event {
+            at_ms: self.clock.elapsed().as_millis() as u64,
+            action,
+        });
+    }
+
+    pub fn finish(mut self, game: &Game) -> Replay {
+        self.replay.stats = game.stats;
+        self.replay.solved = game.is_solved();
+        self.replay
+    }
+}
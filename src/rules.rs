@@ -0,0 +1,272 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, Cell};
+
+// How much a single step can push. The game pushes a whole row of boxes like a train, classic
+// Sokoban only ever moves one box.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RuleSet {
+    Classic,
+    #[default]
+    Chain,
+}
+
+impl RuleSet {
+    pub fn parse(name: &str) -> Option<RuleSet> {
+        match name {
+            "classic" => Some(RuleSet::Classic),
+            "chain" => Some(RuleSet::Chain),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RuleSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleSet::Classic => write!(fmt, "classic"),
+            RuleSet::Chain => write!(fmt, "chain"),
+        }
+    }
+}
+
+// In the order `Board::neighbours` returns the directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    // a letter of the LURD notation, either case
+    pub fn from_char(letter: char) -> Option<Direction> {
+        match letter.to_ascii_lowercase() {
+            'u' => Some(Direction::Up),
+            'r' => Some(Direction::Right),
+            'd' => Some(Direction::Down),
+            'l' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    // lowercase for a step, uppercase for a push
+    pub fn to_char(self, push: bool) -> char {
+        let letter = ['u', 'r', 'd', 'l'][self as usize];
+        if push {
+            letter.to_ascii_uppercase()
+        } else {
+            letter
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Illegal {
+    NotAMove,
+    NoPlayer,
+    Wall,
+    TooManyBoxes,
+}
+
+impl fmt::Display for Illegal {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Illegal::NotAMove => write!(fmt, "not one of u, r, d, l"),
+            Illegal::NoPlayer => write!(fmt, "the level has no player"),
+            Illegal::Wall => write!(fmt, "the player or a box would move into a wall"),
+            Illegal::TooManyBoxes => write!(fmt, "only one box can be pushed at a time"),
+        }
+    }
+}
+
+// What a single step moved, the pushed boxes as (from, to) cells.
+pub struct Step {
+    pub direction: Direction,
+    pub from: usize,
+    pub to: usize,
+    pub pushed: Vec<(usize, usize)>,
+}
+
+impl Step {
+    pub fn is_push(&self) -> bool {
+        !self.pushed.is_empty()
+    }
+}
+
+// Moves the player one cell, pushing the boxes in front of it as far as `rules` allow. Leaves the
+// board as it was when the step is not allowed.
+pub fn step(board: &mut Board, direction: Direction, rules: RuleSet) -> Result<Step, Illegal> {
+    let from = board.player.ok_or(Illegal::NoPlayer)?;
+
+    // the player, the row of boxes in front of it and the free cell they move onto
+    let mut row = vec![from];
+    loop {
+        let last = row[row.len() - 1];
+        let cell = match board.neighbours(last)[direction as usize] {
+            Some(cell) if board.cells[cell] == Cell::Floor => cell,
+            _ => return Err(Illegal::Wall),
+        };
+        row.push(cell);
+        if board.boxes[cell].is_none() {
+            break;
+        }
+        if rules == RuleSet::Classic && row.len() > 2 {
+            return Err(Illegal::TooManyBoxes);
+        }
+    }
+
+    // the furthest box moves first so every box moves onto a free cell
+    let mut pushed = Vec::new();
+    for pair in row[1..].windows(2).rev() {
+        board.boxes[pair[1]] = board.boxes[pair[0]].take();
+        pushed.push((pair[0], pair[1]));
+    }
+
+    let to = row[1];
+    board.player = Some(to);
+
+    Ok(Step {
+        direction,
+        from,
+        to,
+        pushed,
+    })
+}
+
+// every box on a spot of its own colour
+pub fn is_solved(board: &Board) -> bool {
+    board
+        .boxes
+        .iter()
+        .zip(board.goals.iter())
+        .all(|(block, goal)| block.is_none() || block == goal)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Stats {
+    pub moves: usize,
+    pub pushes: usize,
+}
+
+// `step` counts the move letters from 1, whitespace is skipped
+#[derive(Debug)]
+pub struct MoveError {
+    pub step: usize,
+    pub letter: char,
+    pub reason: Illegal,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "move {} ({}): {}", self.step, self.letter, self.reason)
+    }
+}
+
+impl Error for MoveError {}
+
+// Plays a LURD string on the board, calling `after_step` after every move. Stops at the first
+// move that is not allowed. Letters may be of either case, a push is counted when boxes move.
+pub fn play(
+    board: &mut Board,
+    moves: &str,
+    rules: RuleSet,
+    mut after_step: impl FnMut(&Board, &Step, Stats),
+) -> Result<Stats, MoveError> {
+    let mut stats = Stats::default();
+
+    for (index, letter) in moves.chars().filter(|letter| !letter.is_whitespace()).enumerate() {
+        let error = |reason| MoveError {
+            step: index + 1,
+            letter,
+            reason,
+        };
+        let direction = Direction::from_char(letter).ok_or_else(|| error(Illegal::NotAMove))?;
+        let step = step(board, direction, rules).map_err(error)?;
+
+        stats.moves += 1;
+        if step.is_push() {
+            stats.pushes += 1;
+        }
+        after_step(board, &step, stats);
+    }
+
+    Ok(stats)
+}
+
+// A level being played with the positions before every move, for undo, redo and restart.
+pub struct Game {
+    pub board: Board,
+    pub rules: RuleSet,
+    pub stats: Stats,
+    history: Vec<(Board, Stats)>,
+    // positions taken back by undo
+    undone: Vec<(Board, Stats)>,
+}
+
+impl Game {
+    pub fn new(board: Board, rules: RuleSet) -> Game {
+        Game {
+            board,
+            rules,
+            stats: Stats::default(),
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    pub fn step(&mut self, direction: Direction) -> Result<Step, Illegal> {
+        let before = self.board.clone();
+        let step = step(&mut self.board, direction, self.rules)?;
+        self.history.push((before, self.stats));
+        self.undone.clear();
+
+        self.stats.moves += 1;
+        if step.is_push() {
+            self.stats.pushes += 1;
+        }
+        Ok(step)
+    }
+
+    // takes back the last move and its count, false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some((board, stats)) => {
+                let current = std::mem::replace(&mut self.board, board);
+                self.undone.push((current, self.stats));
+                self.stats = stats;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // makes the last undone move again, false when there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some((board, stats)) => {
+                let before = std::mem::replace(&mut self.board, board);
+                self.history.push((before, self.stats));
+                self.stats = stats;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        if let Some((board, _)) = self.history.drain(..).next() {
+            self.board = board;
+        }
+        self.undone.clear();
+        self.stats = Stats::default();
+    }
+
+    pub fn is_solved(&self) -> bool {
+        is_solved(&self.board)
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use amethyst::core::frame_limiter::{FrameLimiter, FrameRateLimitStrategy};
+use amethyst::prelude::*;
+use amethyst::window::Window;
+use amethyst::winit::dpi::LogicalSize;
+use ron::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::rules::RuleSet;
+
+pub const RESOLUTIONS: [(u32, u32); 6] = [
+    (800, 600),
+    (1024, 768),
+    (1280, 720),
+    (1280, 960),
+    (1600, 900),
+    (1920, 1080),
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    pub gameplay: GameplaySettings,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DisplaySettings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub theme: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub mute_sfx: bool,
+    pub mute_music: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameplaySettings {
+    pub animation_speed: f32,
+    pub key_repeat: bool,
+    pub rules: RuleSet,
+}
+
+impl AudioSettings {
+    pub fn sfx(&self) -> f32 {
+        if self.mute_sfx {
+            0.
+        } else {
+            self.master_volume * self.sfx_volume
+        }
+    }
+
+    pub fn music(&self) -> f32 {
+        if self.mute_music {
+            0.
+        } else {
+            self.master_volume * self.music_volume
+        }
+    }
+}
+
+// The shipped defaults and the user's overrides. The user file only contains the values that
+// differ from the defaults, so changed defaults still reach players who never touched them.
+pub struct SettingsFiles {
+    pub defaults: PathBuf,
+    pub user: PathBuf,
+}
+
+impl SettingsFiles {
+    pub fn load(&self) -> amethyst::Result<Settings> {
+        let mut settings = read_value(&self.defaults)?;
+        if self.user.exists() {
+            merge(&mut settings, read_value(&self.user)?);
+        }
+
+        Ok(settings.into_rust()?)
+    }
+
+    pub fn save(&self, settings: &Settings) -> amethyst::Result<()> {
+        let defaults = read_value(&self.defaults)?;
+        let current = ron::de::from_str(&ron::ser::to_string(settings)?)?;
+        let overrides = diff(current, &defaults).unwrap_or_else(|| Value::Map(Default::default()));
+
+        if let Some(dir) = self.user.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            &self.user,
+            ron::ser::to_string_pretty(&overrides, Default::default())?,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn read_value(path: &Path) -> amethyst::Result<Value> {
+    Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Map(base), Value::Map(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn diff(current: Value, defaults: &Value) -> Option<Value> {
+    match (current, defaults) {
+        (Value::Map(current), Value::Map(defaults)) => {
+            let changed = current
+                .into_iter()
+                .filter_map(|(key, value)| match defaults.get(&key) {
+                    Some(default) => diff(value, default).map(|value| (key, value)),
+                    None => Some((key, value)),
+                })
+                .collect::<BTreeMap<_, _>>();
+
+            if changed.is_empty() {
+                None
+            } else {
+                Some(Value::Map(changed))
+            }
+        }
+        (current, defaults) if &current == defaults => None,
+        (current, _) => Some(current),
+    }
+}
+
+pub fn apply_settings(world: &World) {
+    let settings = world.read_resource::<Settings>();
+
+    if let Some(window) = world.try_fetch::<Window>() {
+        let (width, height) = settings.display.resolution;
+        window.set_inner_size(LogicalSize::new(width as f64, height as f64));
+        window.set_fullscreen(if settings.display.fullscreen {
+            Some(window.get_current_monitor())
+        } else {
+            None
+        });
+    }
+
+    // the renderer does not expose the swapchain present mode,
+    // so vsync is approximated by capping the frame rate instead
+    let mut frame_limiter = world.write_resource::<FrameLimiter>();
+    if settings.display.vsync {
+        frame_limiter.set_rate(
+            FrameRateLimitStrategy::SleepAndYield(Duration::from_millis(2)),
+            60,
+        );
+    } else {
+        frame_limiter.set_rate(FrameRateLimitStrategy::Unlimited, 0);
+    }
+}
@@ -0,0 +1,61 @@
+use roxmltree::{Document, Node};
+
+use crate::level::LevelPack;
+
+// Reads an SLC collection:
+//
+//     <SokobanLevels>
+//       <Title>..</Title> <Description>..</Description>
+//       <LevelCollection Copyright="..">
+//         <Level Id="1" Width="6" Height="3" Copyright=".."><L>######</L>..</Level>
+//
+// The collection's title, author, copyright and description and each level's id and copyright
+// are kept for the level select. Rows are in the standard format.
+pub fn parse(name: String, contents: &str) -> Result<LevelPack, String> {
+    let document = Document::parse(contents).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !root.has_tag_name("SokobanLevels") {
+        return Err(format!(
+            "expected <SokobanLevels>, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut pack = LevelPack {
+        name,
+        title: child_text(root, "Title"),
+        author: child_text(root, "Author"),
+        description: child_text(root, "Description"),
+        ..Default::default()
+    };
+
+    let collection = root
+        .children()
+        .find(|node| node.has_tag_name("LevelCollection"))
+        .ok_or("missing <LevelCollection>")?;
+    pack.copyright = collection.attribute("Copyright").map(str::to_string);
+
+    for level in collection.children().filter(|node| node.has_tag_name("Level")) {
+        let rows = level
+            .children()
+            .filter(|node| node.has_tag_name("L"))
+            .map(|row| row.text().unwrap_or(""))
+            .collect::<Vec<_>>();
+
+        pack.add_level(
+            level.attribute("Id").map(str::to_string),
+            level.attribute("Copyright").map(str::to_string),
+            &rows.join("\n"),
+        );
+    }
+
+    Ok(pack)
+}
+
+fn child_text(node: Node, tag: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(tag))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
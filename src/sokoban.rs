@@ -1,31 +1,46 @@
+use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Display;
 
 use amethyst::animation::AnimationSetPrefab;
-use amethyst::assets::{Handle, Prefab, PrefabData, PrefabLoader, ProgressCounter, RonFormat};
+use amethyst::assets::{PrefabData, ProgressCounter};
 use amethyst::core::ecs::Entity;
+use amethyst::core::Hidden;
+use amethyst::core::shrev::EventChannel;
 use amethyst::derive::PrefabData;
 use amethyst::error::Error;
-use amethyst::renderer::sprite::prefab::{SpriteScenePrefab, SpriteSheetPrefab};
-use amethyst::ui::{Anchor, LineMode, TtfFormat, UiText, UiTransform};
+use amethyst::input::{is_close_requested, InputEvent};
+use amethyst::renderer::sprite::prefab::SpriteScenePrefab;
+use amethyst::ui::{Anchor, LineMode, UiText, UiTransform};
 use amethyst::{
-    assets::{AssetStorage, Loader},
+    assets::AssetStorage,
     core::transform::Transform,
     prelude::*,
-    renderer::{Camera, ImageFormat, SpriteRender, SpriteSheet, SpriteSheetFormat, Texture},
+    renderer::{Camera, SpriteRender},
 };
 use serde::{Deserialize, Serialize};
 
-use crate::components::register_components;
-use crate::map::load_map;
-use std::collections::HashMap;
-use amethyst::audio::{SourceHandle, WavFormat};
-use amethyst::utils::fps_counter::FpsCounter;
-use amethyst::core::Time;
+use crate::assets::current_theme;
+use crate::board::Board;
+use crate::components::{register_components, Direction, Position};
+use crate::level::{LevelPacks, LevelRef};
+use crate::map::{load_map, MapSize, TILE_WIDTH};
+use crate::music::play_pack_music;
+use crate::events::GameEvent;
+use crate::replay::Replay;
+use crate::settings::Settings;
+use crate::theme::cycle_theme;
+use crate::sounds::{SoundBank, SoundEvent};
+use amethyst::audio::{output::Output, Source};
+use amethyst::config::ConfigError;
+use amethyst::utils::application_root_dir;
 
 pub const WINDOW_HEIGHT: f32 = 600.0;
 pub const WINDOW_WIDTH: f32 = 800.0;
 
+// seconds between the moves of a replay being watched
+const REPLAY_STEP: f32 = 0.2;
+
 #[derive(Eq, PartialOrd, PartialEq, Hash, Debug, Copy, Clone, Deserialize, Serialize)]
 pub enum AnimationId {
     Idle,
@@ -33,48 +48,260 @@ pub enum AnimationId {
 
 #[derive(Debug, Clone, Deserialize, PrefabData)]
 pub struct MyPrefabData {
-    sprite_scene: SpriteScenePrefab,
+    // the sprite comes from the tileset, prefabs normally only carry the animation
+    #[serde(default)]
+    sprite_scene: Option<SpriteScenePrefab>,
     animation_set: AnimationSetPrefab<AnimationId, SpriteRender>,
 }
 
-pub struct Sokoban;
+// gameplay systems only run while a level is being played
+#[derive(Default, PartialEq)]
+pub enum CurrentState {
+    #[default]
+    Menu,
+    Level,
+    Editor,
+}
+
+pub struct Sokoban {
+    level: Option<LevelRef>,
+    board: Option<Board>,
+    // the moves of a replay to watch instead of playing, in LURD notation
+    replay: Option<String>,
+}
+
+impl Sokoban {
+    pub fn new(level: LevelRef) -> Self {
+        Sokoban {
+            level: Some(level),
+            board: None,
+            replay: None,
+        }
+    }
+
+    // plays a level that is not part of any pack, from the editor or the generator
+    pub fn from_board(board: Board) -> Self {
+        Sokoban {
+            level: None,
+            board: Some(board),
+            replay: None,
+        }
+    }
+}
 
 impl SimpleState for Sokoban {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
         register_components(world);
-        initialise_camera(world);
 
-        let assets = load_assets(world);
-        initialise_level(world, &assets);
+        if self.board.is_none() {
+            self.board = self.level.map(|level| level_board(world, level));
+        }
+        load_map(world, self.board.as_ref().expect("a level or a board to play"));
+        initialise_camera(world);
 
-        world.insert(Gameplay::default());
+        let mut gameplay = Gameplay {
+            level: self.level,
+            replayed: self.replay.is_some(),
+            ..Default::default()
+        };
+        if gameplay.replayed {
+            gameplay.transition(GameplayState::Replaying);
+        }
+        world.insert(gameplay);
+        world.insert(ReplayPlayback::new(self.replay.as_deref().unwrap_or_default()));
+        world.insert(MoveHistory::default());
+        world.insert(CurrentState::Level);
 
         initialise_ui(world);
         initialise_audio(world);
+
+        // levels from the editor get the default playlist
+        let pack = match self.level {
+            Some(level) => world.read_resource::<LevelPacks>().packs[level.pack].name.clone(),
+            None => String::new(),
+        };
+        play_pack_music(world, &pack);
+
+        world
+            .write_resource::<EventChannel<GameEvent>>()
+            .single_write(GameEvent::LevelStarted);
     }
 
-    fn update(&mut self, state_data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
-        let world = &state_data.world;
-        if world.read_resource::<Time>().frame_number() % 20 == 0 {
-            let fps = world.read_resource::<FpsCounter>().sampled_fps();
-            println!("{}", fps);
-        }
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.delete_all();
+        data.world.remove::<GameUi>();
+        data.world.insert(CurrentState::Menu);
+    }
 
-        Trans::None
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+        let state = world.read_resource::<Gameplay>().state;
+
+        match event {
+            StateEvent::Window(event) if is_close_requested(&event) => Trans::Quit,
+            StateEvent::Input(InputEvent::ActionPressed(action)) => match action.as_str() {
+                "back" => Trans::Pop,
+                "test_play" if self.level.is_none() => Trans::Pop,
+                "confirm" if state == GameplayState::Won => {
+                    let next = self
+                        .level
+                        .and_then(|level| world.read_resource::<LevelPacks>().next(level));
+                    match next {
+                        Some(next) => Trans::Switch(Box::new(Sokoban::new(next))),
+                        None => Trans::Pop,
+                    }
+                }
+                "restart" => {
+                    play_sound_event(world, SoundEvent::Restart);
+                    Trans::Switch(Box::new(Sokoban {
+                        level: self.level,
+                        board: self.board.clone(),
+                        replay: None,
+                    }))
+                }
+                "replay" if state != GameplayState::Replaying => {
+                    let rules = world.read_resource::<Settings>().gameplay.rules;
+                    let board = self.board.as_ref().expect("a level or a board to play");
+                    let replay = application_root_dir()
+                        .ok()
+                        .and_then(|root| Replay::latest(&root.join("user").join("replays"), board, rules));
+                    match replay {
+                        Some(replay) => Trans::Switch(Box::new(Sokoban {
+                            level: self.level,
+                            board: self.board.clone(),
+                            replay: Some(replay.moves()),
+                        })),
+                        None => {
+                            log::info!("no replay of this level in user/replays");
+                            Trans::None
+                        }
+                    }
+                }
+                "next_theme" => {
+                    cycle_theme(world, 1);
+                    Trans::None
+                }
+                "pause" => {
+                    let (to, event) = match state {
+                        GameplayState::Paused => (GameplayState::Playing, GameEvent::Resumed),
+                        _ => (GameplayState::Paused, GameEvent::Paused),
+                    };
+                    if world.write_resource::<Gameplay>().transition(to) {
+                        world
+                            .write_resource::<EventChannel<GameEvent>>()
+                            .single_write(event);
+                    }
+                    Trans::None
+                }
+                _ => Trans::None,
+            },
+            _ => Trans::None,
+        }
     }
+
 }
 
 #[derive(Default)]
 pub struct Gameplay {
+    pub level: Option<LevelRef>,
     pub state: GameplayState,
     pub moves_count: u32,
+    pub pushes_count: u32,
+    // started from a replay, so winning does not count as solving the level
+    pub replayed: bool,
 }
 
+impl Gameplay {
+    // returns false and keeps the current state if the change is not allowed
+    pub fn transition(&mut self, to: GameplayState) -> bool {
+        if self.state.can_transition(to) {
+            self.state = to;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// The moves of a replay being watched, one every `REPLAY_STEP` seconds. Empty when the level is
+// played.
+#[derive(Default)]
+pub struct ReplayPlayback {
+    moves: VecDeque<Direction>,
+    timer: f32,
+}
+
+impl ReplayPlayback {
+    pub fn new(moves: &str) -> Self {
+        ReplayPlayback {
+            moves: moves.chars().filter_map(Direction::from_char).collect(),
+            timer: REPLAY_STEP,
+        }
+    }
+
+    // the next move once it is due
+    pub fn next(&mut self, delta_seconds: f32) -> Option<Direction> {
+        self.timer -= delta_seconds;
+        if self.timer > 0. {
+            return None;
+        }
+
+        self.timer += REPLAY_STEP;
+        self.moves.pop_front()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+// The steps made in the level, newest last, so they can be taken back.
+#[derive(Default)]
+pub struct MoveHistory {
+    pub steps: Vec<MadeStep>,
+}
+
+pub struct MadeStep {
+    // the board before the step
+    pub board: Board,
+    // each entity moved with where it came from and went to, in the order they moved
+    pub moved: Vec<(Entity, Position, Position)>,
+    pub pushed: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum GameplayState {
+    #[default]
     Playing,
     Won,
+    Lost,
+    Paused,
+    Replaying,
+}
+
+impl GameplayState {
+    pub fn can_transition(self, to: GameplayState) -> bool {
+        use GameplayState::*;
+
+        matches!(
+            (self, to),
+            (Playing, Won)
+                | (Playing, Lost)
+                | (Playing, Paused)
+                | (Paused, Playing)
+                | (Lost, Playing)
+                | (Playing, Replaying)
+                | (Replaying, Playing)
+                | (Replaying, Won)
+                | (Replaying, Lost)
+        )
+    }
 }
 
 impl Display for GameplayState {
@@ -82,20 +309,27 @@ impl Display for GameplayState {
         fmt.write_str(match self {
             GameplayState::Playing => "Playing",
             GameplayState::Won => "Won",
+            GameplayState::Lost => "Stuck",
+            GameplayState::Paused => "Paused",
+            GameplayState::Replaying => "Replay",
         })?;
         Ok(())
     }
 }
 
-impl Default for GameplayState {
-    fn default() -> Self {
-        Self::Playing
-    }
-}
+pub fn initialise_camera(world: &mut World) {
+    let (map_width, map_height) = {
+        let map_size = world.read_resource::<MapSize>();
+        (map_size.width as f32, map_size.height as f32)
+    };
 
-fn initialise_camera(world: &mut World) {
+    // keep the level centred in the window
     let mut transform = Transform::default();
-    transform.set_translation_xyz(WINDOW_WIDTH * 0.5, WINDOW_HEIGHT * 0.5, 20.0);
+    transform.set_translation_xyz(
+        map_width * TILE_WIDTH * 0.5,
+        map_height * TILE_WIDTH * 0.5,
+        20.0,
+    );
 
     world
         .create_entity()
@@ -104,125 +338,47 @@ fn initialise_camera(world: &mut World) {
         .build();
 }
 
-fn initialise_level(world: &mut World, assets: &ImageAssets) {
-    const MAP: &str = "
-    N N W W W W W W
-    W W W . . . . W
-    W . . . BB . . W
-    W . . RB . . . W
-    W . P . . . . W
-    W . . . . RS . W
-    W . . BS . . . W
-    W . . . . . . W
-    W W W W W W W W
-    ";
-
-    load_map(world, MAP.to_string(), assets);
-}
-
-pub struct ImageAssets {
-    pub player_sprite: SpriteRender,
-    pub box_red_sprite: SpriteRender,
-    pub box_blue_sprite: SpriteRender,
-    pub box_spot_red_sprite: SpriteRender,
-    pub box_spot_blue_sprite: SpriteRender,
-    pub wall_sprite: SpriteRender,
-    pub floor_sprite: SpriteRender,
-}
-
-pub struct Sounds {
-    pub correct: SourceHandle,
-    pub incorrect: SourceHandle,
-    pub wall: SourceHandle,
+fn level_board(world: &World, level: LevelRef) -> Board {
+    world
+        .read_resource::<LevelPacks>()
+        .get(level)
+        .map(|level| level.board.clone())
+        .expect("level exists in the loaded level packs")
 }
 
-fn load_audio(loader: &Loader, world: &World, file: &str) -> SourceHandle {
-    loader.load(file, WavFormat, (), &world.read_resource())
+fn play_sound_event(world: &World, event: SoundEvent) {
+    let volume = world.read_resource::<Settings>().audio.sfx();
+    world.read_resource::<SoundBank>().play(
+        event,
+        volume,
+        &world.read_resource::<AssetStorage<Source>>(),
+        world.try_fetch::<Output>().as_deref(),
+    );
 }
 
 fn initialise_audio(world: &mut World) {
-    let sound_effects = {
-        let loader = world.read_resource::<Loader>();
-
-        let sound = Sounds {
-            correct: load_audio(&loader, &world, "sounds/correct.wav"),
-            incorrect: load_audio(&loader, &world, "sounds/incorrect.wav"),
-            wall: load_audio(&loader, &world, "sounds/wall.wav"),
-        };
-
-        sound
-    };
-
-    world.insert(sound_effects);
-}
-
-fn load_assets(world: &mut World) -> ImageAssets {
-    ImageAssets {
-        player_sprite: create_sprite_render(world, "images/player.png", "images/sprite_32x32.ron"),
-        box_red_sprite: create_sprite_render(
-            world,
-            "images/box_red.png",
-            "images/sprite_32x32.ron",
-        ),
-        box_blue_sprite: create_sprite_render(
-            world,
-            "images/box_blue.png",
-            "images/sprite_32x32.ron",
-        ),
-        box_spot_red_sprite: create_sprite_render(
-            world,
-            "images/box_spot_red.png",
-            "images/sprite_32x32.ron",
-        ),
-        box_spot_blue_sprite: create_sprite_render(
-            world,
-            "images/box_spot_blue.png",
-            "images/sprite_32x32.ron",
-        ),
-        wall_sprite: create_sprite_render(world, "images/wall.png", "images/sprite_32x32.ron"),
-        floor_sprite: create_sprite_render(world, "images/floor.png", "images/sprite_32x32.ron"),
-    }
-}
-
-fn create_sprite_render(
-    world: &mut World,
-    asset_path: &str,
-    sprite_definition_path: &str,
-) -> SpriteRender {
-    let loader = world.read_resource::<Loader>();
-    let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-
-    let texture_handle = loader.load(asset_path, ImageFormat::default(), (), &texture_storage);
-
-    let sheet_handle = {
-        let loader = world.read_resource::<Loader>();
-        let sheet_storage = world.read_resource::<AssetStorage<SpriteSheet>>();
-        loader.load(
-            sprite_definition_path,
-            SpriteSheetFormat(texture_handle),
-            (),
-            &sheet_storage,
-        )
-    };
-
-    SpriteRender {
-        sprite_sheet: sheet_handle.clone(),
-        sprite_number: 0,
-    }
+    // read on every level start so sound changes show up without restarting the game
+    let sound_bank = application_root_dir()
+        .map(|root| root.join("assets").join("sounds").join("sounds.ron"))
+        .map_err(ConfigError::from)
+        .and_then(|path| SoundBank::load(world, &path))
+        .unwrap_or_else(|e| {
+            log::error!("could not load sounds: {}", e);
+            SoundBank::default()
+        });
+
+    world.insert(sound_bank);
 }
 
 pub struct GameUi {
     pub moves_element: Entity,
     pub gameplay_state_element: Entity,
+    pub debug_element: Entity,
 }
 
 fn initialise_ui(world: &mut World) {
-    let font = world.read_resource::<Loader>().load(
-        "font/square.ttf",
-        TtfFormat,
-        (),
-        &world.read_resource(),
-    );
+    let theme = current_theme(world);
+    let font = theme.font.clone();
 
     let moves_transform = UiTransform::new(
         "MOVES".to_string(),
@@ -252,7 +408,7 @@ fn initialise_ui(world: &mut World) {
         .with(UiText::new(
             font.clone(),
             "0".to_string(),
-            [1., 1., 1., 1.],
+            theme.text_colour,
             50.,
             LineMode::Single,
             Anchor::Middle,
@@ -265,15 +421,41 @@ fn initialise_ui(world: &mut World) {
         .with(UiText::new(
             font.clone(),
             "0".to_string(),
-            [1., 1., 1., 1.],
+            theme.text_colour,
             50.,
             LineMode::Single,
             Anchor::Middle,
         ))
         .build();
 
+    let debug_transform = UiTransform::new(
+        "DEBUG".to_string(),
+        Anchor::TopLeft,
+        Anchor::TopLeft,
+        10.,
+        -10.,
+        2.,
+        600.,
+        300.,
+    );
+
+    let debug_element = world
+        .create_entity()
+        .with(debug_transform)
+        .with(UiText::new(
+            font,
+            String::new(),
+            [1., 1., 0., 1.],
+            14.,
+            LineMode::Wrap,
+            Anchor::TopLeft,
+        ))
+        .with(Hidden)
+        .build();
+
     world.insert(GameUi {
         moves_element,
         gameplay_state_element,
+        debug_element,
     });
 }
@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset_files::read_ron;
+use crate::board::Board;
+use crate::canonical::level_hash;
+use crate::level::LevelInfo;
+use crate::rules::{is_solved, play, MoveError, RuleSet, Stats};
+
+// The fewest moves and pushes known for a level, either may be left out.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Best {
+    pub moves: Option<usize>,
+    pub pushes: Option<usize>,
+}
+
+// One entry of a solutions file. `level` is the title of the level in its pack or its level
+// hash, which keeps working when levels are renamed and tells apart levels with the same title.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StoredSolution {
+    pub level: String,
+    pub moves: String,
+    #[serde(default)]
+    pub best: Best,
+}
+
+#[derive(Debug)]
+pub enum LookupError {
+    Missing,
+    // titles need not be unique within a pack, the hash is
+    Ambiguous(usize),
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LookupError::Missing => write!(fmt, "no level in the pack has this title or hash"),
+            LookupError::Ambiguous(count) => write!(
+                fmt,
+                "{} levels have this title, name the level by its hash",
+                count
+            ),
+        }
+    }
+}
+
+impl StoredSolution {
+    // The index of the level this solution is for, by hash first and by title otherwise.
+    pub fn find(&self, levels: &[LevelInfo]) -> Result<usize, LookupError> {
+        if let Some(index) = levels
+            .iter()
+            .position(|level| self.level == level_hash(&level.board))
+        {
+            return Ok(index);
+        }
+
+        let titled = levels
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| self.level == level.title)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        match titled.as_slice() {
+            [] => Err(LookupError::Missing),
+            [index] => Ok(*index),
+            _ => Err(LookupError::Ambiguous(titled.len())),
+        }
+    }
+}
+
+// A RON list of solutions, usually kept next to the pack it belongs to.
+pub fn load(path: &Path) -> Result<Vec<StoredSolution>, String> {
+    read_ron(path)
+}
+
+pub enum Verdict {
+    Solved(Stats),
+    // better than the stored best in the counts given, which should be updated
+    NewBest(Stats, Best),
+    // worse than the stored best in every count it has
+    NotOptimal(Stats, Best),
+    NotSolved(Stats),
+    Invalid(MoveError),
+}
+
+impl Verdict {
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Verdict::NotOptimal(..) | Verdict::NotSolved(_) | Verdict::Invalid(_)
+        )
+    }
+}
+
+impl fmt::Display for Best {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let counts = [(self.moves, "moves"), (self.pushes, "pushes")]
+            .iter()
+            .filter_map(|(count, name)| count.map(|count| format!("{} {}", count, name)))
+            .collect::<Vec<_>>();
+        fmt.write_str(&counts.join(", "))
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let counts = |stats: &Stats| format!("{} moves, {} pushes", stats.moves, stats.pushes);
+        match self {
+            Verdict::Solved(stats) => write!(fmt, "ok, {}", counts(stats)),
+            Verdict::NewBest(stats, best) => {
+                write!(fmt, "ok, {}, a new best of {}", counts(stats), best)
+            }
+            Verdict::NotOptimal(stats, best) => {
+                write!(fmt, "not optimal, {} where the best is {}", counts(stats), best)
+            }
+            Verdict::NotSolved(stats) => write!(fmt, "does not solve the level, {}", counts(stats)),
+            Verdict::Invalid(e) => write!(fmt, "invalid, {}", e),
+        }
+    }
+}
+
+// Plays the solution and compares its counts with the stored best. The fewest moves and the fewest
+// pushes often come from different solutions, so each count is compared on its own: a solution
+// passes when it is as good as the best in at least one of them.
+pub fn verify(board: &Board, solution: &StoredSolution, rules: RuleSet) -> Verdict {
+    let mut board = board.clone();
+    let stats = match play(&mut board, &solution.moves, rules, |_, _, _| ()) {
+        Ok(stats) => stats,
+        Err(e) => return Verdict::Invalid(e),
+    };
+    if !is_solved(&board) {
+        return Verdict::NotSolved(stats);
+    }
+
+    let compare = |count: usize, best: Option<usize>| best.map(|best| count.cmp(&best));
+    let comparisons = [
+        compare(stats.moves, solution.best.moves),
+        compare(stats.pushes, solution.best.pushes),
+    ];
+    let improved = |ordering: Option<Ordering>, count: usize| {
+        Some(count).filter(|_| ordering == Some(Ordering::Less))
+    };
+    let new_best = Best {
+        moves: improved(comparisons[0], stats.moves),
+        pushes: improved(comparisons[1], stats.pushes),
+    };
+
+    if new_best.moves.is_some() || new_best.pushes.is_some() {
+        Verdict::NewBest(stats, new_best)
+    } else if comparisons.contains(&Some(Ordering::Equal)) || comparisons == [None, None] {
+        Verdict::Solved(stats)
+    } else {
+        Verdict::NotOptimal(stats, solution.best)
+    }
+}
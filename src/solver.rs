@@ -0,0 +1,246 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use crate::board::{Board, BoxColour, Cell, COLOURS};
+use crate::rules::{step, Direction, RuleSet};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+pub struct Solution {
+    pub moves: String,
+    pub pushes: usize,
+}
+
+impl Solution {
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+}
+
+// Boxes sorted by cell, boxes of the same colour cannot be told apart.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    player: usize,
+    boxes: Vec<(usize, BoxColour)>,
+}
+
+struct Node {
+    state: State,
+    parent: Option<usize>,
+    // the steps to the pushing position followed by the push
+    moves: String,
+}
+
+// Finds a solution with the fewest pushes, and the fewest moves among those, pushing boxes the
+// way `rules` allow. Gives up with `None` after looking at `limit` positions or when the level
+// cannot be solved.
+pub fn solve(board: &Board, rules: RuleSet, limit: usize) -> Option<Solution> {
+    let player = board.player?;
+    let live = COLOURS
+        .iter()
+        .map(|&colour| (colour, live_squares(board, colour)))
+        .collect::<HashMap<_, _>>();
+
+    let start = State {
+        player,
+        boxes: boxes_of(board),
+    };
+
+    let mut nodes = vec![Node {
+        state: start.clone(),
+        parent: None,
+        moves: String::new(),
+    }];
+    let mut best = HashMap::new();
+    best.insert(start, (0, 0));
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((0, 0, 0)));
+
+    while let Some(Reverse((pushes, moves, id))) = queue.pop() {
+        let state = nodes[id].state.clone();
+        if best.get(&state).is_some_and(|&cost| cost < (pushes, moves)) {
+            continue;
+        }
+
+        if is_solved(board, &state) {
+            return Some(Solution {
+                moves: path(&nodes, id),
+                pushes,
+            });
+        }
+        if nodes.len() > limit {
+            return None;
+        }
+
+        let walk = walk(board, &state);
+        let position = state.board(board);
+
+        for &(cell, _) in state.boxes.iter() {
+            let neighbours = board.neighbours(cell);
+            for (index, &direction) in DIRECTIONS.iter().enumerate() {
+                // the player stands on the opposite side of the box
+                let from = match neighbours[(index + 2) % 4] {
+                    Some(from) => from,
+                    None => continue,
+                };
+                let steps = match &walk[from] {
+                    Some(steps) => steps,
+                    None => continue,
+                };
+
+                let mut pushed = position.clone();
+                pushed.player = Some(from);
+                let moved = match step(&mut pushed, direction, rules) {
+                    Ok(moved) => moved,
+                    Err(_) => continue,
+                };
+                let dead = moved.pushed.iter().any(|&(_, to)| {
+                    pushed.boxes[to].is_some_and(|colour| !live[&colour][to])
+                });
+                if dead {
+                    continue;
+                }
+
+                let next = State {
+                    player: moved.to,
+                    boxes: boxes_of(&pushed),
+                };
+
+                let cost = (pushes + 1, moves + steps.len() + 1);
+                if best.get(&next).is_some_and(|&known| known <= cost) {
+                    continue;
+                }
+                best.insert(next.clone(), cost);
+
+                let mut moves = steps.clone();
+                moves.push(direction.to_char(true));
+                nodes.push(Node {
+                    state: next,
+                    parent: Some(id),
+                    moves,
+                });
+                queue.push(Reverse((cost.0, cost.1, nodes.len() - 1)));
+            }
+        }
+    }
+
+    None
+}
+
+impl State {
+    // the level with the boxes and the player where this state has them
+    fn board(&self, board: &Board) -> Board {
+        let mut position = board.clone();
+        position.boxes = vec![None; board.cells.len()];
+        for &(cell, colour) in self.boxes.iter() {
+            position.boxes[cell] = Some(colour);
+        }
+        position.player = Some(self.player);
+        position
+    }
+}
+
+fn boxes_of(board: &Board) -> Vec<(usize, BoxColour)> {
+    board
+        .boxes
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &colour)| colour.map(|colour| (index, colour)))
+        .collect()
+}
+
+fn is_solved(board: &Board, state: &State) -> bool {
+    state
+        .boxes
+        .iter()
+        .all(|&(cell, colour)| board.goals[cell] == Some(colour))
+}
+
+fn path(nodes: &[Node], mut id: usize) -> String {
+    let mut parts = Vec::new();
+    loop {
+        parts.push(nodes[id].moves.as_str());
+        match nodes[id].parent {
+            Some(parent) => id = parent,
+            None => break,
+        }
+    }
+    parts.reverse();
+    parts.concat()
+}
+
+// The shortest walk from the player to every cell, without moving any box.
+fn walk(board: &Board, state: &State) -> Vec<Option<String>> {
+    let mut paths = vec![None; board.cells.len()];
+    let mut queue = VecDeque::new();
+    paths[state.player] = Some(String::new());
+    queue.push_back(state.player);
+
+    while let Some(index) = queue.pop_front() {
+        for (direction, next) in board.neighbours(index).iter().enumerate() {
+            let next = match next {
+                Some(next) => *next,
+                None => continue,
+            };
+            let blocked = board.cells[next] != Cell::Floor
+                || state.boxes.iter().any(|&(cell, _)| cell == next);
+            if blocked || paths[next].is_some() {
+                continue;
+            }
+
+            let mut steps = paths[index].clone().unwrap_or_default();
+            steps.push(DIRECTIONS[direction].to_char(false));
+            paths[next] = Some(steps);
+            queue.push_back(next);
+        }
+    }
+
+    paths
+}
+
+// Cells from which a box of `colour` can still be pushed onto one of its spots, ignoring other
+// boxes. Found by pulling boxes away from the spots.
+pub fn live_squares(board: &Board, colour: BoxColour) -> Vec<bool> {
+    let mut live = vec![false; board.cells.len()];
+    let mut queue = VecDeque::new();
+    for (index, goal) in board.goals.iter().enumerate() {
+        if *goal == Some(colour) && board.cells[index] == Cell::Floor {
+            live[index] = true;
+            queue.push_back(index);
+        }
+    }
+
+    let floor = |index: Option<usize>| index.filter(|&index| board.cells[index] == Cell::Floor);
+    while let Some(index) = queue.pop_front() {
+        for direction in 0..4 {
+            // pulling moves the box onto the player's cell and the player one further
+            let box_to = match floor(board.neighbours(index)[direction]) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            if floor(board.neighbours(box_to)[direction]).is_some() && !live[box_to] {
+                live[box_to] = true;
+                queue.push_back(box_to);
+            }
+        }
+    }
+
+    live
+}
+
+// Floor cells from which no box can be pushed onto a spot of any colour.
+pub fn dead_squares(board: &Board) -> Vec<bool> {
+    let live = COLOURS
+        .iter()
+        .map(|&colour| live_squares(board, colour))
+        .collect::<Vec<_>>();
+
+    (0..board.cells.len())
+        .map(|index| board.cells[index] == Cell::Floor && !live.iter().any(|live| live[index]))
+        .collect()
+}
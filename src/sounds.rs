@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use amethyst::assets::{AssetStorage, Loader};
+use amethyst::audio::output::Output;
+use amethyst::audio::{OggFormat, Source, SourceHandle, WavFormat};
+use amethyst::config::{Config, ConfigError};
+use amethyst::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::assets::GameAssets;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SoundEvent {
+    Step,
+    Push,
+    WallBump,
+    CorrectSpot,
+    WrongSpot,
+    Undo,
+    Restart,
+    LevelWon,
+    Deadlock,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SoundFile {
+    pub file: String,
+    #[serde(default = "full_volume")]
+    pub volume: f32,
+}
+
+fn full_volume() -> f32 {
+    1.
+}
+
+// One of `sounds` is picked at random every time the event fires, and its volume is scaled by a
+// random factor in `1 - volume_variation ..= 1`.
+#[derive(Deserialize, Serialize)]
+pub struct SoundMapping {
+    pub sounds: Vec<SoundFile>,
+    #[serde(default)]
+    pub volume_variation: f32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SoundConfig {
+    pub events: HashMap<SoundEvent, SoundMapping>,
+}
+
+struct LoadedSound {
+    handle: SourceHandle,
+    volume: f32,
+}
+
+struct LoadedMapping {
+    sounds: Vec<LoadedSound>,
+    volume_variation: f32,
+}
+
+#[derive(Default)]
+pub struct SoundBank {
+    mappings: HashMap<SoundEvent, LoadedMapping>,
+}
+
+impl SoundBank {
+    pub fn load(world: &World, config_path: &Path) -> Result<SoundBank, ConfigError> {
+        let config = SoundConfig::load(config_path)?.events;
+        let loader = world.read_resource::<Loader>();
+        let storage = world.read_resource::<AssetStorage<Source>>();
+        let preloaded = world.try_fetch::<GameAssets>();
+
+        let mappings = config
+            .into_iter()
+            .map(|(event, mapping)| {
+                let sounds = mapping
+                    .sounds
+                    .into_iter()
+                    .map(|sound| LoadedSound {
+                        // files that are not in the asset manifest are loaded on demand
+                        handle: if let Some(handle) = preloaded
+                            .as_ref()
+                            .and_then(|assets| assets.sounds.get(&sound.file))
+                        {
+                            handle.clone()
+                        } else if sound.file.ends_with(".ogg") {
+                            loader.load(sound.file, OggFormat, (), &storage)
+                        } else {
+                            loader.load(sound.file, WavFormat, (), &storage)
+                        },
+                        volume: sound.volume,
+                    })
+                    .collect();
+
+                let mapping = LoadedMapping {
+                    sounds,
+                    volume_variation: mapping.volume_variation.clamp(0., 1.),
+                };
+                (event, mapping)
+            })
+            .collect();
+
+        Ok(SoundBank { mappings })
+    }
+
+    pub fn play(
+        &self,
+        event: SoundEvent,
+        volume: f32,
+        storage: &AssetStorage<Source>,
+        output: Option<&Output>,
+    ) {
+        let mapping = match self.mappings.get(&event) {
+            Some(mapping) => mapping,
+            None => return,
+        };
+
+        let mut rng = rand::thread_rng();
+        if let Some(sound) = mapping.sounds.choose(&mut rng) {
+            let variation = if mapping.volume_variation > 0. {
+                1. - rng.gen_range(0., mapping.volume_variation)
+            } else {
+                1.
+            };
+            play_sound(&sound.handle, volume * sound.volume * variation, storage, output);
+        }
+    }
+}
+
+pub fn play_sound(
+    sound: &SourceHandle,
+    volume: f32,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+) {
+    if let Some(output) = output {
+        if let Some(sound) = storage.get(sound) {
+            output.play_once(sound, volume);
+        }
+    }
+}
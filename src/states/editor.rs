@@ -0,0 +1,516 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use amethyst::core::ecs::{Entity, Join};
+use amethyst::core::math::{Point3, Vector2};
+use amethyst::core::Transform;
+use amethyst::input::{is_close_requested, InputEvent, InputHandler, StringBindings};
+use amethyst::prelude::*;
+use amethyst::renderer::Camera;
+use amethyst::ui::{Anchor, Interactable, LineMode, UiEvent, UiEventType, UiText, UiTransform};
+use amethyst::utils::application_root_dir;
+use amethyst::window::ScreenDimensions;
+use amethyst::winit::MouseButton;
+
+use crate::board::{Board, BoxColour, Cell, COLOURS};
+use crate::components::{register_components, Position};
+use crate::level::split_levels;
+use crate::map::{load_map, TILE_WIDTH};
+use crate::music::play_menu_music;
+use crate::sokoban::{initialise_camera, CurrentState, Sokoban};
+use crate::states::menu::{ui_style, UiStyle};
+use crate::theme::cycle_theme;
+
+const MIN_SIZE: usize = 3;
+// what fits into the window next to the palette
+const MAX_WIDTH: usize = 24;
+const MAX_HEIGHT: usize = 18;
+const HISTORY: usize = 100;
+const PALETTE_ROW_HEIGHT: f32 = 30.;
+const HELP: &str = "F5 test  Ctrl+S save  Ctrl+O load  Ctrl+Z undo\nShift+arrows resize  Tab next tile";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PaletteTile {
+    Wall,
+    Floor,
+    Box(BoxColour),
+    Spot(BoxColour),
+    Player,
+}
+
+impl PaletteTile {
+    fn label(self) -> String {
+        match self {
+            PaletteTile::Wall => "Wall".to_string(),
+            PaletteTile::Floor => "Floor".to_string(),
+            PaletteTile::Box(colour) => format!("Box ({})", colour),
+            PaletteTile::Spot(colour) => format!("Spot ({})", colour),
+            PaletteTile::Player => "Player".to_string(),
+        }
+    }
+}
+
+fn palette() -> Vec<PaletteTile> {
+    let mut tiles = vec![PaletteTile::Wall, PaletteTile::Floor];
+    for &colour in COLOURS.iter() {
+        tiles.push(PaletteTile::Box(colour));
+        tiles.push(PaletteTile::Spot(colour));
+    }
+    tiles.push(PaletteTile::Player);
+    tiles
+}
+
+struct EditorUi {
+    palette: Vec<Entity>,
+    status: Entity,
+}
+
+// Paints a level with the mouse: the left button draws the selected palette tile, the right
+// button erases back to outside. The level is kept in `user/editor.txt`.
+pub struct EditorState {
+    board: Board,
+    palette: Vec<PaletteTile>,
+    selected: usize,
+    history: Vec<Board>,
+    painting: bool,
+    over_palette: bool,
+    message: String,
+    ui: Option<EditorUi>,
+}
+
+impl Default for EditorState {
+    fn default() -> Self {
+        EditorState {
+            board: empty_board(9, 7),
+            palette: palette(),
+            selected: 0,
+            history: Vec::new(),
+            painting: false,
+            over_palette: false,
+            message: String::new(),
+            ui: None,
+        }
+    }
+}
+
+impl EditorState {
+    fn show(&mut self, world: &mut World) {
+        register_components(world);
+        world.insert(CurrentState::Editor);
+        self.create_ui(world);
+        self.rebuild(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world.delete_all();
+        self.ui = None;
+    }
+
+    // the level is redrawn from scratch on every change, which also autotiles the walls
+    fn rebuild(&mut self, world: &mut World) {
+        let stale = {
+            let entities = world.entities();
+            let positions = world.read_storage::<Position>();
+            let cameras = world.read_storage::<Camera>();
+            let mut stale = (&entities, &positions)
+                .join()
+                .map(|(entity, _)| entity)
+                .collect::<Vec<_>>();
+            stale.extend((&entities, &cameras).join().map(|(entity, _)| entity));
+            stale
+        };
+        world
+            .delete_entities(&stale)
+            .expect("level entities are alive");
+
+        load_map(world, &self.board);
+        initialise_camera(world);
+        self.refresh_ui(world);
+    }
+
+    fn create_ui(&mut self, world: &mut World) {
+        let style = ui_style(world);
+
+        let palette = self
+            .palette
+            .iter()
+            .enumerate()
+            .map(|(index, tile)| {
+                let entity = create_label(
+                    world,
+                    &style,
+                    &tile.label(),
+                    Anchor::TopLeft,
+                    -10. - index as f32 * PALETTE_ROW_HEIGHT,
+                );
+                world
+                    .write_storage::<Interactable>()
+                    .insert(entity, Interactable)
+                    .expect("palette entry was just created");
+                entity
+            })
+            .collect();
+        let status = create_label(world, &style, "", Anchor::BottomLeft, 10.);
+
+        self.ui = Some(EditorUi { palette, status });
+    }
+
+    fn refresh_ui(&self, world: &mut World) {
+        let ui = match &self.ui {
+            Some(ui) => ui,
+            None => return,
+        };
+
+        let style = ui_style(world);
+        let mut texts = world.write_storage::<UiText>();
+        for (index, &entity) in ui.palette.iter().enumerate() {
+            if let Some(text) = texts.get_mut(entity) {
+                text.font = style.font.clone();
+                text.color = if index == self.selected {
+                    style.selected_colour
+                } else {
+                    style.text_colour
+                };
+            }
+        }
+
+        let errors = self.board.errors();
+        let validation = if errors.is_empty() {
+            "ready to play".to_string()
+        } else {
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        if let Some(text) = texts.get_mut(ui.status) {
+            text.font = style.font.clone();
+            text.color = style.text_colour;
+            text.text = format!(
+                "{}x{}  {}\n{}\n{}",
+                self.board.width, self.board.height, self.message, validation, HELP
+            );
+        }
+    }
+
+    fn edit(&mut self, world: &mut World, change: impl FnOnce(&mut Board)) {
+        let before = self.board.clone();
+        change(&mut self.board);
+        if self.board != before {
+            self.remember(before);
+            self.rebuild(world);
+        }
+    }
+
+    fn remember(&mut self, board: Board) {
+        self.history.push(board);
+        if self.history.len() > HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    fn undo(&mut self, world: &mut World) {
+        if let Some(board) = self.history.pop() {
+            self.board = board;
+            self.rebuild(world);
+        }
+    }
+
+    fn resize(&mut self, world: &mut World, width: isize, height: isize) {
+        let width = (self.board.width as isize + width).clamp(MIN_SIZE as isize, MAX_WIDTH as isize);
+        let height =
+            (self.board.height as isize + height).clamp(MIN_SIZE as isize, MAX_HEIGHT as isize);
+        self.edit(world, |board| board.resize(width as usize, height as usize));
+    }
+
+    fn paint(&mut self, world: &mut World, index: usize, erase: bool) {
+        let tile = self.palette[self.selected];
+
+        // a whole stroke is undone at once, so only the first painted cell saves the board
+        let before = self.board.clone();
+        let board = &mut self.board;
+        if erase {
+            clear(board, index);
+            board.cells[index] = Cell::Outside;
+        } else {
+            paint_tile(board, index, tile);
+        }
+
+        if self.board != before {
+            if !self.painting {
+                self.remember(before);
+            }
+            self.painting = true;
+            self.rebuild(world);
+        }
+    }
+
+    fn save(&mut self, world: &mut World) {
+        let contents = format!("; Editor\n{}\n", self.board.to_xsb());
+        let result = editor_file().and_then(|path| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, contents)?;
+            Ok(path)
+        });
+
+        self.message = match result {
+            Ok(path) => format!("saved to {}", path.display()),
+            Err(e) => {
+                log::error!("could not save the level: {}", e);
+                format!("could not save: {}", e)
+            }
+        };
+        self.refresh_ui(world);
+    }
+
+    fn load(&mut self, world: &mut World) {
+        match load_board() {
+            Ok(board) => {
+                let previous = std::mem::replace(&mut self.board, board);
+                self.remember(previous);
+                self.message = "loaded".to_string();
+                self.rebuild(world);
+            }
+            Err(e) => {
+                log::error!("could not load the level: {}", e);
+                self.message = format!("could not load: {}", e);
+                self.refresh_ui(world);
+            }
+        }
+    }
+
+    fn cell_under_mouse(&self, world: &World) -> Option<usize> {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let (x, y) = input.mouse_position()?;
+        let screen = world.read_resource::<ScreenDimensions>();
+        let cameras = world.read_storage::<Camera>();
+        let transforms = world.read_storage::<Transform>();
+        let (camera, camera_transform) = (&cameras, &transforms).join().next()?;
+
+        let point = camera.screen_to_world_point(
+            Point3::new(x, y, 0.),
+            Vector2::new(screen.width(), screen.height()),
+            camera_transform,
+        );
+        if point.x < 0. || point.y < 0. {
+            return None;
+        }
+
+        // rows count from the top of the level, world coordinates from the bottom
+        let (column, from_bottom) = ((point.x / TILE_WIDTH) as usize, (point.y / TILE_WIDTH) as usize);
+        if column >= self.board.width || from_bottom >= self.board.height {
+            return None;
+        }
+        Some(self.board.index(column, self.board.height - 1 - from_bottom))
+    }
+}
+
+impl SimpleState for EditorState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        match load_board() {
+            Ok(board) => self.board = board,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => {
+                log::error!("could not load the level: {}", e);
+                self.message = format!("could not load: {}", e);
+            }
+        }
+
+        play_menu_music(world);
+        self.show(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+        data.world.insert(CurrentState::Menu);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        play_menu_music(data.world);
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+
+        match event {
+            StateEvent::Window(event) if is_close_requested(&event) => Trans::Quit,
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                match action.as_str() {
+                    "back" => return Trans::Pop,
+                    "test_play" => {
+                        if self.board.errors().is_empty() {
+                            return Trans::Push(Box::new(Sokoban::from_board(self.board.clone())));
+                        }
+                        self.message = "fix the level before playing it".to_string();
+                        self.refresh_ui(world);
+                    }
+                    "next_tile" => {
+                        self.selected = (self.selected + 1) % self.palette.len();
+                        self.refresh_ui(world);
+                    }
+                    "next_theme" => {
+                        cycle_theme(world, 1);
+                        self.refresh_ui(world);
+                    }
+                    "undo" => self.undo(world),
+                    "save" => self.save(world),
+                    "load" => self.load(world),
+                    "grow_width" => self.resize(world, 1, 0),
+                    "shrink_width" => self.resize(world, -1, 0),
+                    "grow_height" => self.resize(world, 0, 1),
+                    "shrink_height" => self.resize(world, 0, -1),
+                    _ => (),
+                }
+                Trans::None
+            }
+            StateEvent::Ui(UiEvent { event_type, target }) => {
+                let index = self
+                    .ui
+                    .as_ref()
+                    .and_then(|ui| ui.palette.iter().position(|&entity| entity == target));
+                if let Some(index) = index {
+                    match event_type {
+                        UiEventType::Click => {
+                            self.selected = index;
+                            self.refresh_ui(world);
+                        }
+                        UiEventType::HoverStart => self.over_palette = true,
+                        UiEventType::HoverStop => self.over_palette = false,
+                        _ => (),
+                    }
+                }
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let world = &mut *data.world;
+
+        let (draw, erase) = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            (
+                input.mouse_button_is_down(MouseButton::Left),
+                input.mouse_button_is_down(MouseButton::Right),
+            )
+        };
+
+        if !draw && !erase {
+            self.painting = false;
+        } else if !self.over_palette {
+            if let Some(index) = self.cell_under_mouse(world) {
+                self.paint(world, index, erase);
+            }
+        }
+
+        Trans::None
+    }
+}
+
+fn create_label(world: &mut World, style: &UiStyle, text: &str, anchor: Anchor, y: f32) -> Entity {
+    let transform = UiTransform::new(
+        text.to_string(),
+        anchor,
+        anchor,
+        10.,
+        y,
+        1.,
+        if anchor == Anchor::TopLeft { 160. } else { 520. },
+        if anchor == Anchor::TopLeft {
+            PALETTE_ROW_HEIGHT
+        } else {
+            140.
+        },
+    );
+
+    world
+        .create_entity()
+        .with(transform)
+        .with(UiText::new(
+            style.font.clone(),
+            text.to_string(),
+            style.text_colour,
+            20.,
+            LineMode::Wrap,
+            Anchor::TopLeft,
+        ))
+        .build()
+}
+
+fn clear(board: &mut Board, index: usize) {
+    board.boxes[index] = None;
+    board.goals[index] = None;
+    if board.player == Some(index) {
+        board.player = None;
+    }
+}
+
+fn paint_tile(board: &mut Board, index: usize, tile: PaletteTile) {
+    match tile {
+        PaletteTile::Wall => {
+            clear(board, index);
+            board.cells[index] = Cell::Wall;
+        }
+        PaletteTile::Floor => {
+            clear(board, index);
+            board.cells[index] = Cell::Floor;
+        }
+        PaletteTile::Box(colour) => {
+            board.cells[index] = Cell::Floor;
+            board.boxes[index] = Some(colour);
+            if board.player == Some(index) {
+                board.player = None;
+            }
+        }
+        PaletteTile::Spot(colour) => {
+            board.cells[index] = Cell::Floor;
+            board.goals[index] = Some(colour);
+        }
+        PaletteTile::Player => {
+            board.cells[index] = Cell::Floor;
+            board.boxes[index] = None;
+            board.player = Some(index);
+        }
+    }
+}
+
+// a walled room with the player in the top left corner
+fn empty_board(width: usize, height: usize) -> Board {
+    let mut board = Board::new(width, height);
+    for index in 0..board.cells.len() {
+        let (x, y) = board.position(index);
+        let edge = x == 0 || y == 0 || x + 1 == width || y + 1 == height;
+        board.cells[index] = if edge { Cell::Wall } else { Cell::Floor };
+    }
+    board.player = Some(board.index(1, 1));
+    board
+}
+
+fn editor_file() -> io::Result<PathBuf> {
+    let root = application_root_dir()?;
+    Ok(root.join("user").join("editor.txt"))
+}
+
+fn load_board() -> io::Result<Board> {
+    let contents = fs::read_to_string(editor_file()?)?;
+    let (_, map) = split_levels(&contents)
+        .into_iter()
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no level in the file"))?;
+    Board::parse(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,146 @@
+use amethyst::input::is_close_requested;
+use amethyst::prelude::*;
+
+use crate::level::{LevelPacks, LevelRef};
+use crate::progress::Progress;
+use crate::sokoban::Sokoban;
+use crate::states::menu::{Menu, MenuAction};
+
+#[derive(Default)]
+pub struct LevelSelectState {
+    menu: Option<Menu>,
+    pack: usize,
+    selected: usize,
+}
+
+impl LevelSelectState {
+    fn create_menu(&mut self, world: &mut World, selected: usize) {
+        let mut menu = Menu::new(world, 8);
+
+        let (title, entries) = {
+            let packs = world.read_resource::<LevelPacks>();
+            let progress = world.read_resource::<Progress>();
+
+            let title = match packs.packs.get(self.pack) {
+                Some(pack) => {
+                    let name = match pack.author.as_ref().or(pack.copyright.as_ref()) {
+                        Some(author) => format!("{} by {}", pack.display_name(), author),
+                        None => pack.display_name().to_string(),
+                    };
+                    if packs.packs.len() > 1 {
+                        format!("< {} >", name)
+                    } else {
+                        name
+                    }
+                }
+                None => "No levels found".to_string(),
+            };
+
+            let entries = packs.packs.get(self.pack).map_or_else(Vec::new, |pack| {
+                pack.levels
+                    .iter()
+                    .enumerate()
+                    .map(|(index, level)| {
+                        let level_ref = LevelRef {
+                            pack: self.pack,
+                            index,
+                        };
+                        let marker = if progress.is_solved(&packs, level_ref) {
+                            "[x]"
+                        } else {
+                            "[ ]"
+                        };
+                        // only credit levels that are not by the collection's author
+                        match &level.copyright {
+                            Some(copyright) if Some(copyright) != pack.copyright.as_ref() => {
+                                format!("{} {}. {} ({})", marker, index + 1, level.title, copyright)
+                            }
+                            _ => format!("{} {}. {}", marker, index + 1, level.title),
+                        }
+                    })
+                    .collect()
+            });
+
+            (title, entries)
+        };
+
+        menu.set_title(world, &title);
+        for entry in entries {
+            menu.add_entry(world, &entry, true);
+        }
+        menu.select(world, selected);
+
+        self.menu = Some(menu);
+    }
+
+    fn delete_menu(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+
+    fn switch_pack(&mut self, world: &mut World, offset: isize) {
+        let pack_count = world.read_resource::<LevelPacks>().packs.len() as isize;
+        if pack_count > 1 {
+            self.pack = (self.pack as isize + offset).rem_euclid(pack_count) as usize;
+            self.delete_menu(world);
+            self.create_menu(world, 0);
+        }
+    }
+}
+
+impl SimpleState for LevelSelectState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.create_menu(data.world, 0);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.delete_menu(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(menu) = &self.menu {
+            self.selected = menu.selected();
+        }
+        self.delete_menu(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.create_menu(data.world, self.selected);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let world = data.world;
+        let action = match self.menu.as_mut() {
+            Some(menu) => menu.handle_event(world, &event),
+            None => None,
+        };
+
+        match action {
+            Some(MenuAction::Select(index)) => Trans::Push(Box::new(Sokoban::new(LevelRef {
+                pack: self.pack,
+                index,
+            }))),
+            Some(MenuAction::Left) => {
+                self.switch_pack(world, -1);
+                Trans::None
+            }
+            Some(MenuAction::Right) => {
+                self.switch_pack(world, 1);
+                Trans::None
+            }
+            Some(MenuAction::Back) => Trans::Pop,
+            None => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use amethyst::assets::{Completion, ProgressCounter};
+use amethyst::config::Config;
+use amethyst::core::ecs::Entity;
+use amethyst::input::{is_close_requested, InputEvent};
+use amethyst::prelude::*;
+use amethyst::ui::{Anchor, LineMode, UiImage, UiText, UiTransform};
+use amethyst::utils::application_root_dir;
+
+use crate::asset_files::{load_theme, AssetManifest};
+use crate::assets::GameAssets;
+use crate::settings::apply_settings;
+use crate::theme::apply_theme;
+use crate::states::menu::{create_text, ui_style};
+use crate::states::MainMenuState;
+
+const BAR_WIDTH: f32 = 400.;
+const BAR_HEIGHT: f32 = 20.;
+const ERROR_COLOUR: [f32; 4] = [1., 0.4, 0.4, 1.];
+
+// Loads everything in `assets/manifest.ron` before the main menu shows up. If anything fails the
+// state stays on an error screen naming the broken assets.
+#[derive(Default)]
+pub struct LoadingState {
+    progress: ProgressCounter,
+    assets: Option<GameAssets>,
+    errors: Vec<String>,
+    bar: Option<Entity>,
+    entities: Vec<Entity>,
+}
+
+impl LoadingState {
+    fn start_loading(&mut self, world: &mut World) -> Result<(), Vec<String>> {
+        let assets_dir = application_root_dir()
+            .map_err(|e| vec![e.to_string()])?
+            .join("assets");
+        let path = assets_dir.join("manifest.ron");
+        let manifest = AssetManifest::load(&path)
+            .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+
+        if manifest.themes.is_empty() {
+            return Err(vec!["the asset manifest lists no themes".to_string()]);
+        }
+
+        let mut themes = Vec::new();
+        let mut errors = Vec::new();
+        for theme_path in &manifest.themes {
+            match load_theme(&assets_dir, theme_path) {
+                Ok(theme) => themes.push(theme),
+                Err(error) => errors.push(error),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.assets = Some(GameAssets::load(world, &manifest, &themes, &mut self.progress));
+        Ok(())
+    }
+
+    fn create_progress_bar(&mut self, world: &mut World) {
+        let style = ui_style(world);
+        let title = create_text(world, &style.font, "Loading", 0., 40., style.text_colour);
+
+        let background = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_background".to_string(),
+                Anchor::Middle,
+                Anchor::MiddleLeft,
+                -BAR_WIDTH / 2.,
+                0.,
+                1.,
+                BAR_WIDTH,
+                BAR_HEIGHT,
+            ))
+            .with(UiImage::SolidColor([0.2, 0.2, 0.2, 1.]))
+            .build();
+
+        let bar = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_bar".to_string(),
+                Anchor::Middle,
+                Anchor::MiddleLeft,
+                -BAR_WIDTH / 2.,
+                0.,
+                2.,
+                0.,
+                BAR_HEIGHT,
+            ))
+            .with(UiImage::SolidColor([1., 0.85, 0.2, 1.]))
+            .build();
+
+        self.bar = Some(bar);
+        self.entities.extend(vec![title, background, bar]);
+    }
+
+    fn show_errors(&mut self, world: &mut World) {
+        world.delete_entities(&self.entities).ok();
+        self.entities.clear();
+
+        let font = ui_style(world).font;
+        let title = create_text(world, &font, "Could not load the game", 0., 200., ERROR_COLOUR);
+
+        let mut message = self.errors.join("\n");
+        message.push_str("\n\nPress Esc to quit.");
+        let details = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_errors".to_string(),
+                Anchor::Middle,
+                Anchor::TopMiddle,
+                0.,
+                160.,
+                1.,
+                700.,
+                400.,
+            ))
+            .with(UiText::new(
+                font,
+                message,
+                [1., 1., 1., 1.],
+                16.,
+                LineMode::Wrap,
+                Anchor::TopLeft,
+            ))
+            .build();
+
+        self.entities.extend(vec![title, details]);
+    }
+}
+
+impl SimpleState for LoadingState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        apply_settings(world);
+
+        match self.start_loading(world) {
+            Ok(()) => self.create_progress_bar(world),
+            Err(errors) => {
+                self.errors = errors;
+                self.show_errors(world);
+            }
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.delete_entities(&self.entities).ok();
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        match event {
+            StateEvent::Window(event) if is_close_requested(&event) => Trans::Quit,
+            StateEvent::Input(InputEvent::ActionPressed(action))
+                if !self.errors.is_empty() && (action == "back" || action == "confirm") =>
+            {
+                Trans::Quit
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if !self.errors.is_empty() {
+            return Trans::None;
+        }
+
+        let world = &mut data.world;
+        match self.progress.complete() {
+            Completion::Loading => {
+                let total = self.progress.num_assets().max(1) as f32;
+                let done = self.progress.num_finished() as f32 / total;
+                if let Some(bar) = self.bar {
+                    if let Some(transform) = world.write_storage::<UiTransform>().get_mut(bar) {
+                        transform.width = BAR_WIDTH * done;
+                    }
+                }
+                Trans::None
+            }
+            Completion::Failed => {
+                self.errors = self
+                    .progress
+                    .errors()
+                    .into_iter()
+                    .map(|error| format!("{}: {}", error.asset_name, error.error))
+                    .collect();
+                self.show_errors(world);
+                Trans::None
+            }
+            Completion::Complete => {
+                if let Some(assets) = self.assets.take() {
+                    world.insert(assets);
+                    apply_theme(world);
+                }
+                Trans::Switch(Box::new(MainMenuState::default()))
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use amethyst::input::is_close_requested;
+use amethyst::prelude::*;
+
+use crate::generator::{generate, GeneratorSettings};
+use crate::level::LevelPacks;
+use crate::music::play_menu_music;
+use crate::progress::Progress;
+use crate::settings::Settings;
+use crate::sokoban::Sokoban;
+use crate::states::menu::{Menu, MenuAction};
+use crate::states::{EditorState, LevelSelectState, SettingsState};
+
+const CONTINUE: usize = 0;
+const LEVEL_SELECT: usize = 1;
+const RANDOM_LEVEL: usize = 2;
+const EDITOR: usize = 3;
+const SETTINGS: usize = 4;
+const QUIT: usize = 5;
+
+#[derive(Default)]
+pub struct MainMenuState {
+    menu: Option<Menu>,
+}
+
+impl MainMenuState {
+    fn create_menu(&mut self, world: &mut World) {
+        let has_unsolved = {
+            let packs = world.read_resource::<LevelPacks>();
+            world.read_resource::<Progress>().continue_level(&packs).is_some()
+        };
+
+        let mut menu = Menu::new(world, 6);
+        menu.set_title(world, "SOKOBAN");
+        menu.add_entry(world, "Continue", has_unsolved);
+        menu.add_entry(world, "Level select", true);
+        menu.add_entry(world, "Random level", true);
+        menu.add_entry(world, "Editor", true);
+        menu.add_entry(world, "Settings", true);
+        menu.add_entry(world, "Quit", true);
+
+        self.menu = Some(menu);
+    }
+
+    fn delete_menu(&mut self, world: &mut World) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(world);
+        }
+    }
+}
+
+impl SimpleState for MainMenuState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        play_menu_music(data.world);
+        self.create_menu(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.delete_menu(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.delete_menu(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        play_menu_music(data.world);
+        self.create_menu(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let world = data.world;
+        let action = match self.menu.as_mut() {
+            Some(menu) => menu.handle_event(world, &event),
+            None => None,
+        };
+
+        match action {
+            Some(MenuAction::Select(CONTINUE)) => {
+                let packs = world.read_resource::<LevelPacks>();
+                match world.read_resource::<Progress>().continue_level(&packs) {
+                    Some(level) => Trans::Push(Box::new(Sokoban::new(level))),
+                    None => Trans::None,
+                }
+            }
+            Some(MenuAction::Select(LEVEL_SELECT)) => {
+                Trans::Push(Box::new(LevelSelectState::default()))
+            }
+            Some(MenuAction::Select(RANDOM_LEVEL)) => {
+                let settings = GeneratorSettings {
+                    rules: world.read_resource::<Settings>().gameplay.rules,
+                    ..Default::default()
+                };
+                match generate(&settings, rand::random()) {
+                    Some(generated) => Trans::Push(Box::new(Sokoban::from_board(generated.board))),
+                    None => {
+                        log::error!("could not generate a level");
+                        Trans::None
+                    }
+                }
+            }
+            Some(MenuAction::Select(EDITOR)) => Trans::Push(Box::new(EditorState::default())),
+            Some(MenuAction::Select(SETTINGS)) => Trans::Push(Box::new(SettingsState::default())),
+            Some(MenuAction::Select(QUIT)) => Trans::Quit,
+            _ => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use amethyst::assets::Loader;
+use amethyst::core::ecs::Entity;
+use amethyst::core::Hidden;
+use amethyst::input::InputEvent;
+use amethyst::prelude::*;
+use amethyst::ui::{
+    Anchor, FontHandle, Interactable, LineMode, TtfFormat, UiEvent, UiEventType, UiText,
+    UiTransform,
+};
+
+use crate::assets::{current_theme, GameAssets};
+
+const ENTRY_HEIGHT: f32 = 40.;
+
+// Font and colours of menu text, from the current theme.
+#[derive(Clone)]
+pub struct UiStyle {
+    pub font: FontHandle,
+    pub text_colour: [f32; 4],
+    pub selected_colour: [f32; 4],
+    pub disabled_colour: [f32; 4],
+}
+
+pub enum MenuAction {
+    Select(usize),
+    Left,
+    Right,
+    Back,
+}
+
+struct MenuEntry {
+    entity: Entity,
+    enabled: bool,
+}
+
+// A vertical list of text entries driven by the "up", "down", "left", "right", "confirm" and
+// "back" input actions (keyboard or gamepad) as well as mouse hover and click.
+pub struct Menu {
+    style: UiStyle,
+    title: Option<Entity>,
+    entries: Vec<MenuEntry>,
+    selected: usize,
+    scroll: usize,
+    visible_rows: usize,
+}
+
+impl Menu {
+    pub fn new(world: &mut World, visible_rows: usize) -> Menu {
+        Menu {
+            style: ui_style(world),
+            title: None,
+            entries: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            visible_rows,
+        }
+    }
+
+    pub fn set_title(&mut self, world: &mut World, title: &str) {
+        match self.title {
+            Some(entity) => set_text(world, entity, title),
+            None => {
+                let entity = create_text(world, &self.style.font, title, 0., 0., self.style.text_colour);
+                self.title = Some(entity);
+                self.refresh(world);
+            }
+        }
+    }
+
+    pub fn add_entry(&mut self, world: &mut World, label: &str, enabled: bool) -> usize {
+        let entity = create_text(world, &self.style.font, label, 0., 0., self.style.text_colour);
+        world
+            .write_storage::<Interactable>()
+            .insert(entity, Interactable)
+            .expect("menu entry was just created");
+
+        if enabled && !self.is_enabled(self.selected) {
+            self.selected = self.entries.len();
+        }
+        self.entries.push(MenuEntry { entity, enabled });
+
+        self.refresh(world);
+        self.entries.len() - 1
+    }
+
+    pub fn set_label(&self, world: &mut World, index: usize, label: &str) {
+        set_text(world, self.entries[index].entity, label);
+    }
+
+    pub fn select(&mut self, world: &mut World, index: usize) {
+        self.selected = index.min(self.entries.len().saturating_sub(1));
+        self.refresh(world);
+    }
+
+    // picks up a changed theme
+    pub fn restyle(&mut self, world: &mut World) {
+        self.style = ui_style(world);
+        if let Some(title) = self.title {
+            if let Some(text) = world.write_storage::<UiText>().get_mut(title) {
+                text.font = self.style.font.clone();
+                text.color = self.style.text_colour;
+            }
+        }
+        self.refresh(world);
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn clear_entries(&mut self, world: &mut World) {
+        let entities = self.entries.drain(..).map(|entry| entry.entity);
+        world
+            .delete_entities(&entities.collect::<Vec<_>>())
+            .expect("menu entries are alive");
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    pub fn delete(&mut self, world: &mut World) {
+        self.clear_entries(world);
+        if let Some(title) = self.title.take() {
+            world.delete_entity(title).expect("menu title is alive");
+        }
+    }
+
+    pub fn handle_event(&mut self, world: &mut World, event: &StateEvent) -> Option<MenuAction> {
+        match event {
+            StateEvent::Input(InputEvent::ActionPressed(action)) => match action.as_str() {
+                "up" => {
+                    self.step(world, -1);
+                    None
+                }
+                "down" => {
+                    self.step(world, 1);
+                    None
+                }
+                "left" => Some(MenuAction::Left),
+                "right" => Some(MenuAction::Right),
+                "confirm" if self.is_enabled(self.selected) => {
+                    Some(MenuAction::Select(self.selected))
+                }
+                "back" => Some(MenuAction::Back),
+                _ => None,
+            },
+            StateEvent::Ui(UiEvent { event_type, target }) => {
+                let index = self.entries.iter().position(|e| e.entity == *target)?;
+                if !self.entries[index].enabled {
+                    return None;
+                }
+
+                match event_type {
+                    UiEventType::HoverStart => {
+                        self.select(world, index);
+                        None
+                    }
+                    UiEventType::Click => Some(MenuAction::Select(index)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_enabled(&self, index: usize) -> bool {
+        self.entries.get(index).is_some_and(|entry| entry.enabled)
+    }
+
+    fn step(&mut self, world: &mut World, direction: isize) {
+        let len = self.entries.len() as isize;
+        let mut index = self.selected as isize;
+        for _ in 0..len {
+            index = (index + direction).rem_euclid(len);
+            if self.entries[index as usize].enabled {
+                self.select(world, index as usize);
+                return;
+            }
+        }
+    }
+
+    fn refresh(&mut self, world: &mut World) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.visible_rows {
+            self.scroll = self.selected + 1 - self.visible_rows;
+        }
+
+        let mut texts = world.write_storage::<UiText>();
+        let mut transforms = world.write_storage::<UiTransform>();
+        let mut hidden = world.write_storage::<Hidden>();
+
+        // centre the visible rows vertically, with the title above them
+        let rows = self.entries.len().min(self.visible_rows);
+        let top = rows.saturating_sub(1) as f32 * ENTRY_HEIGHT * 0.5;
+        if let Some(transform) = self.title.and_then(|title| transforms.get_mut(title)) {
+            transform.local_y = top + 1.5 * ENTRY_HEIGHT;
+        }
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            if let Some(text) = texts.get_mut(entry.entity) {
+                text.font = self.style.font.clone();
+                text.color = if !entry.enabled {
+                    self.style.disabled_colour
+                } else if index == self.selected {
+                    self.style.selected_colour
+                } else {
+                    self.style.text_colour
+                };
+            }
+
+            let row = index as isize - self.scroll as isize;
+            if row < 0 || row >= self.visible_rows as isize {
+                hidden.insert(entry.entity, Hidden).unwrap();
+            } else {
+                hidden.remove(entry.entity);
+                if let Some(transform) = transforms.get_mut(entry.entity) {
+                    transform.local_y = top - row as f32 * ENTRY_HEIGHT;
+                }
+            }
+        }
+    }
+}
+
+pub fn ui_style(world: &World) -> UiStyle {
+    if world.has_value::<GameAssets>() {
+        let theme = current_theme(world);
+        return UiStyle {
+            font: theme.font,
+            text_colour: theme.text_colour,
+            selected_colour: theme.selected_colour,
+            disabled_colour: theme.disabled_colour,
+        };
+    }
+
+    // the loading screen shows up before any theme has been loaded
+    UiStyle {
+        font: world
+            .read_resource::<Loader>()
+            .load("font/square.ttf", TtfFormat, (), &world.read_resource()),
+        text_colour: [1., 1., 1., 1.],
+        selected_colour: [1., 0.85, 0.2, 1.],
+        disabled_colour: [0.4, 0.4, 0.4, 1.],
+    }
+}
+
+pub fn create_text(
+    world: &mut World,
+    font: &FontHandle,
+    text: &str,
+    x: f32,
+    y: f32,
+    colour: [f32; 4],
+) -> Entity {
+    let transform = UiTransform::new(
+        text.to_string(),
+        Anchor::Middle,
+        Anchor::Middle,
+        x,
+        y,
+        1.,
+        600.,
+        ENTRY_HEIGHT,
+    );
+
+    world
+        .create_entity()
+        .with(transform)
+        .with(UiText::new(
+            font.clone(),
+            text.to_string(),
+            colour,
+            30.,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build()
+}
+
+pub fn set_text(world: &mut World, entity: Entity, text: &str) {
+    if let Some(ui_text) = world.write_storage::<UiText>().get_mut(entity) {
+        ui_text.text = text.to_string();
+    }
+}
@@ -0,0 +1,12 @@
+pub use self::editor::EditorState;
+pub use self::level_select::LevelSelectState;
+pub use self::loading::LoadingState;
+pub use self::main_menu::MainMenuState;
+pub use self::settings::SettingsState;
+
+mod editor;
+mod level_select;
+mod loading;
+mod main_menu;
+pub mod menu;
+mod settings;
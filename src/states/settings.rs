@@ -0,0 +1,188 @@
+use amethyst::input::is_close_requested;
+use amethyst::prelude::*;
+
+use crate::rules::RuleSet;
+use crate::settings::{apply_settings, Settings, SettingsFiles, RESOLUTIONS};
+use crate::states::menu::{Menu, MenuAction};
+use crate::theme::cycle_theme;
+
+const RESOLUTION: usize = 0;
+const FULLSCREEN: usize = 1;
+const VSYNC: usize = 2;
+const THEME: usize = 3;
+const MASTER_VOLUME: usize = 4;
+const SFX_VOLUME: usize = 5;
+const MUTE_SFX: usize = 6;
+const MUSIC_VOLUME: usize = 7;
+const MUTE_MUSIC: usize = 8;
+const ANIMATION_SPEED: usize = 9;
+const KEY_REPEAT: usize = 10;
+const PUSH_RULES: usize = 11;
+const BACK: usize = 12;
+
+const VOLUME_STEP: f32 = 0.1;
+const ANIMATION_SPEED_STEP: f32 = 0.25;
+
+#[derive(Default)]
+pub struct SettingsState {
+    menu: Option<Menu>,
+}
+
+impl SettingsState {
+    fn refresh_labels(&self, world: &mut World) {
+        let settings = (*world.read_resource::<Settings>()).clone();
+        if let Some(menu) = &self.menu {
+            for index in RESOLUTION..BACK {
+                menu.set_label(world, index, &label(&settings, index));
+            }
+        }
+    }
+
+    fn change(&mut self, world: &mut World, index: usize, step: i32) {
+        if index == THEME {
+            cycle_theme(world, step);
+            if let Some(menu) = self.menu.as_mut() {
+                menu.restyle(world);
+            }
+            self.refresh_labels(world);
+            return;
+        }
+
+        {
+            let mut settings = world.write_resource::<Settings>();
+            adjust(&mut settings, index, step);
+
+            if let Err(e) = world.read_resource::<SettingsFiles>().save(&settings) {
+                log::error!("could not save settings: {}", e);
+            }
+        }
+
+        apply_settings(world);
+        self.refresh_labels(world);
+    }
+}
+
+impl SimpleState for SettingsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        let mut menu = Menu::new(world, 12);
+        menu.set_title(world, "SETTINGS");
+        for _ in RESOLUTION..BACK {
+            menu.add_entry(world, "", true);
+        }
+        menu.add_entry(world, "Back", true);
+
+        self.menu = Some(menu);
+        self.refresh_labels(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(mut menu) = self.menu.take() {
+            menu.delete(data.world);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let world = data.world;
+        let (action, selected) = match self.menu.as_mut() {
+            Some(menu) => (menu.handle_event(world, &event), menu.selected()),
+            None => return Trans::None,
+        };
+
+        match action {
+            Some(MenuAction::Select(BACK)) | Some(MenuAction::Back) => Trans::Pop,
+            Some(MenuAction::Select(index)) => {
+                self.change(world, index, 1);
+                Trans::None
+            }
+            Some(MenuAction::Left) if selected != BACK => {
+                self.change(world, selected, -1);
+                Trans::None
+            }
+            Some(MenuAction::Right) if selected != BACK => {
+                self.change(world, selected, 1);
+                Trans::None
+            }
+            _ => Trans::None,
+        }
+    }
+}
+
+fn label(settings: &Settings, index: usize) -> String {
+    let on_off = |value: bool| if value { "On" } else { "Off" };
+    let percent = |value: f32| format!("{:.0}%", value * 100.);
+
+    match index {
+        RESOLUTION => {
+            let (width, height) = settings.display.resolution;
+            format!("Resolution: < {}x{} >", width, height)
+        }
+        FULLSCREEN => format!("Fullscreen: {}", on_off(settings.display.fullscreen)),
+        VSYNC => format!("VSync: {}", on_off(settings.display.vsync)),
+        THEME => format!("Theme: < {} >", settings.display.theme),
+        MASTER_VOLUME => format!("Master volume: < {} >", percent(settings.audio.master_volume)),
+        SFX_VOLUME => format!("Sound volume: < {} >", percent(settings.audio.sfx_volume)),
+        MUTE_SFX => format!("Sound: {}", on_off(!settings.audio.mute_sfx)),
+        MUSIC_VOLUME => format!("Music volume: < {} >", percent(settings.audio.music_volume)),
+        MUTE_MUSIC => format!("Music: {}", on_off(!settings.audio.mute_music)),
+        ANIMATION_SPEED => format!(
+            "Animation speed: < {:.2}x >",
+            settings.gameplay.animation_speed
+        ),
+        KEY_REPEAT => format!("Key repeat: {}", on_off(settings.gameplay.key_repeat)),
+        PUSH_RULES => match settings.gameplay.rules {
+            RuleSet::Chain => "Push: a whole row of boxes".to_string(),
+            RuleSet::Classic => "Push: one box at a time".to_string(),
+        },
+        _ => String::new(),
+    }
+}
+
+fn adjust(settings: &mut Settings, index: usize, step: i32) {
+    let volume = |value: f32| {
+        let value = value + step as f32 * VOLUME_STEP;
+        ((value / VOLUME_STEP).round() * VOLUME_STEP).clamp(0., 1.)
+    };
+
+    match index {
+        RESOLUTION => {
+            let current = RESOLUTIONS
+                .iter()
+                .position(|&resolution| resolution == settings.display.resolution)
+                .unwrap_or(0) as i32;
+            let next = (current + step).rem_euclid(RESOLUTIONS.len() as i32);
+            settings.display.resolution = RESOLUTIONS[next as usize];
+        }
+        FULLSCREEN => settings.display.fullscreen = !settings.display.fullscreen,
+        VSYNC => settings.display.vsync = !settings.display.vsync,
+        MASTER_VOLUME => settings.audio.master_volume = volume(settings.audio.master_volume),
+        SFX_VOLUME => settings.audio.sfx_volume = volume(settings.audio.sfx_volume),
+        MUTE_SFX => settings.audio.mute_sfx = !settings.audio.mute_sfx,
+        MUSIC_VOLUME => settings.audio.music_volume = volume(settings.audio.music_volume),
+        MUTE_MUSIC => settings.audio.mute_music = !settings.audio.mute_music,
+        ANIMATION_SPEED => {
+            settings.gameplay.animation_speed = (settings.gameplay.animation_speed
+                + step as f32 * ANIMATION_SPEED_STEP)
+                .clamp(ANIMATION_SPEED_STEP, 4.)
+        }
+        KEY_REPEAT => settings.gameplay.key_repeat = !settings.gameplay.key_repeat,
+        PUSH_RULES => {
+            settings.gameplay.rules = match settings.gameplay.rules {
+                RuleSet::Chain => RuleSet::Classic,
+                RuleSet::Classic => RuleSet::Chain,
+            }
+        }
+        _ => (),
+    }
+}
@@ -1,7 +1,8 @@
-use amethyst::core::ecs::{System, ReadStorage, WriteStorage, Entities, Join};
+use amethyst::core::ecs::{System, ReadStorage, WriteStorage, Entities, Join, ReadExpect};
 use amethyst::animation::{get_animation_set, AnimationSet, AnimationControlSet, AnimationCommand, EndControl};
 use crate::sokoban::AnimationId;
 use amethyst::renderer::SpriteRender;
+use crate::settings::Settings;
 
 pub struct AnimationSystem {}
 
@@ -10,20 +11,23 @@ impl<'s> System<'s> for AnimationSystem {
         Entities<'s>,
         ReadStorage<'s, AnimationSet<AnimationId, SpriteRender>>,
         WriteStorage<'s, AnimationControlSet<AnimationId, SpriteRender>>,
+        ReadExpect<'s, Settings>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, animation_sets, mut control_sets) = data;
+        let (entities, animation_sets, mut control_sets, settings) = data;
+        let rate = settings.gameplay.animation_speed;
 
         for (entity, animation_set) in (&entities, &animation_sets).join() {
             let control_set = get_animation_set(&mut control_sets, entity).unwrap();
             control_set.add_animation(
                 AnimationId::Idle,
-                &animation_set.get(&AnimationId::Idle).unwrap(),
+                animation_set.get(&AnimationId::Idle).unwrap(),
                 EndControl::Loop(None),
-                1.0,
+                rate,
                 AnimationCommand::Start,
             );
+            control_set.set_rate(AnimationId::Idle, rate);
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,170 @@
+use std::collections::VecDeque;
+
+use amethyst::core::ecs::{
+    Entities, Join, Read, ReadExpect, ReadStorage, ReaderId, System, SystemData, World, Write,
+    WriteStorage,
+};
+use amethyst::core::math::{Point3, Vector2};
+use amethyst::core::shrev::EventChannel;
+use amethyst::core::{Hidden, Time, Transform};
+use amethyst::input::{InputEvent, InputHandler, StringBindings};
+use amethyst::renderer::Camera;
+use amethyst::ui::UiText;
+use amethyst::utils::fps_counter::FpsCounter;
+use amethyst::window::ScreenDimensions;
+
+use crate::components::*;
+use crate::events::MoveEvent;
+use crate::map::TILE_WIDTH;
+use crate::sokoban::GameUi;
+
+const SHOWN_MOVE_EVENTS: usize = 8;
+
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub visible: bool,
+}
+
+// Toggled with F3 while playing a level.
+#[derive(Default)]
+pub struct DebugOverlaySystem {
+    input_reader: Option<ReaderId<InputEvent<StringBindings>>>,
+    move_reader: Option<ReaderId<MoveEvent>>,
+    last_move_events: VecDeque<String>,
+}
+
+impl<'s> System<'s> for DebugOverlaySystem {
+    type SystemData = (
+        Write<'s, DebugOverlay>,
+        Read<'s, EventChannel<InputEvent<StringBindings>>>,
+        Read<'s, EventChannel<MoveEvent>>,
+        Option<Read<'s, GameUi>>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, Hidden>,
+        Read<'s, FpsCounter>,
+        Read<'s, Time>,
+        Read<'s, InputHandler<StringBindings>>,
+        ReadExpect<'s, ScreenDimensions>,
+        Entities<'s>,
+        ReadStorage<'s, Camera>,
+        ReadStorage<'s, Transform>,
+        (
+            ReadStorage<'s, Position>,
+            ReadStorage<'s, Player>,
+            ReadStorage<'s, Box>,
+            ReadStorage<'s, BoxSpot>,
+            ReadStorage<'s, Wall>,
+        ),
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut overlay,
+            input_events,
+            move_events,
+            game_ui,
+            mut ui_text,
+            mut hidden,
+            fps_counter,
+            time,
+            input,
+            screen_dimensions,
+            entities,
+            cameras,
+            transforms,
+            (positions, players, boxes, box_spots, walls),
+        ) = data;
+
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            if let InputEvent::ActionPressed(action) = event {
+                if action == "debug_overlay" {
+                    overlay.visible = !overlay.visible;
+                }
+            }
+        }
+
+        for event in move_events.read(self.move_reader.as_mut().unwrap()) {
+            log::debug!("move event: {:?}", event);
+            self.last_move_events.push_back(format!("{:?}", event));
+            if self.last_move_events.len() > SHOWN_MOVE_EVENTS {
+                self.last_move_events.pop_front();
+            }
+        }
+
+        let element = match game_ui {
+            Some(game_ui) => game_ui.debug_element,
+            None => return,
+        };
+
+        if !overlay.visible {
+            if !hidden.contains(element) {
+                hidden.insert(element, Hidden).unwrap();
+            }
+            return;
+        }
+        hidden.remove(element);
+
+        let player_position = (&players, &positions)
+            .join()
+            .next()
+            .map_or_else(|| "-".to_string(), |(_, position)| format!("{}, {}", position.x, position.y));
+
+        let tile_under_mouse = input
+            .mouse_position()
+            .and_then(|(x, y)| {
+                let (camera, camera_transform) = (&cameras, &transforms).join().next()?;
+                let point = camera.screen_to_world_point(
+                    Point3::new(x, y, 0.),
+                    Vector2::new(screen_dimensions.width(), screen_dimensions.height()),
+                    camera_transform,
+                );
+                if point.x < 0. || point.y < 0. {
+                    return None;
+                }
+                Some(format!(
+                    "{}, {}",
+                    (point.x / TILE_WIDTH) as u32,
+                    (point.y / TILE_WIDTH) as u32
+                ))
+            })
+            .unwrap_or_else(|| "-".to_string());
+
+        let mut lines = vec![
+            format!(
+                "FPS: {:.0}  frame: {:.2} ms",
+                fps_counter.sampled_fps(),
+                time.delta_seconds() * 1000.
+            ),
+            format!(
+                "entities: {}  positions: {}  boxes: {}  spots: {}  walls: {}",
+                entities.join().count(),
+                positions.join().count(),
+                boxes.join().count(),
+                box_spots.join().count(),
+                walls.join().count()
+            ),
+            format!("player: {}", player_position),
+            format!("mouse tile: {}", tile_under_mouse),
+            "last move events:".to_string(),
+        ];
+        lines.extend(self.last_move_events.iter().cloned());
+
+        if let Some(text) = ui_text.get_mut(element) {
+            text.text = lines.join("\n");
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        <Self as System<'_>>::SystemData::setup(world);
+        self.input_reader = Some(
+            world
+                .fetch_mut::<EventChannel<InputEvent<StringBindings>>>()
+                .register_reader(),
+        );
+        self.move_reader = Some(
+            world
+                .fetch_mut::<EventChannel<MoveEvent>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use amethyst::core::ecs::{Read, ReaderId, System, SystemData, World, WriteStorage};
+use amethyst::core::shrev::EventChannel;
+use amethyst::ui::UiText;
+
+use crate::events::{GameEvent, MoveEvent};
+use crate::sokoban::{GameUi, Gameplay};
+
+// Only touches the texts when something they show has changed.
+#[derive(Default)]
+pub struct GameUiSystem {
+    move_reader: Option<ReaderId<MoveEvent>>,
+    game_reader: Option<ReaderId<GameEvent>>,
+}
+
+impl<'s> System<'s> for GameUiSystem {
+    type SystemData = (
+        Read<'s, Gameplay>,
+        Read<'s, EventChannel<MoveEvent>>,
+        Read<'s, EventChannel<GameEvent>>,
+        WriteStorage<'s, UiText>,
+        Option<Read<'s, GameUi>>,
+    );
+
+    fn run(&mut self, (gameplay, move_events, game_events, mut ui_text, game_ui): Self::SystemData) {
+        let moved = move_events.read(self.move_reader.as_mut().unwrap()).count() > 0;
+        let state_changed = game_events.read(self.game_reader.as_mut().unwrap()).count() > 0;
+
+        let game_ui = match game_ui {
+            Some(game_ui) => game_ui,
+            None => return,
+        };
+
+        if moved || state_changed {
+            if let Some(moves_count_ui) = ui_text.get_mut(game_ui.moves_element) {
+                moves_count_ui.text = format!(
+                    "Moves: {} Pushes: {}",
+                    gameplay.moves_count, gameplay.pushes_count
+                );
+            }
+        }
+
+        if state_changed {
+            if let Some(gameplay_state_ui) = ui_text.get_mut(game_ui.gameplay_state_element) {
+                gameplay_state_ui.text = gameplay.state.to_string();
+            }
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        <Self as System<'_>>::SystemData::setup(world);
+        self.move_reader = Some(
+            world
+                .fetch_mut::<EventChannel<MoveEvent>>()
+                .register_reader(),
+        );
+        self.game_reader = Some(
+            world
+                .fetch_mut::<EventChannel<GameEvent>>()
+                .register_reader(),
+        );
+    }
+}
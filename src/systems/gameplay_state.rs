@@ -1,51 +1,91 @@
-use amethyst::core::ecs::{System, Write, ReadStorage, Join, WriteStorage, ReadExpect};
-use crate::sokoban::{Gameplay, GameplayState, GameUi};
-use crate::components::{Position, BoxSpot, Box};
-use std::collections::HashMap;
-use amethyst::ui::UiText;
+use amethyst::core::ecs::{System, Read, Write, ReadStorage, Join, ReaderId, World, SystemData};
 use amethyst::core::shrev::EventChannel;
-use crate::events::{MoveEvent, BoxPlacedOnSpot};
+use crate::sokoban::{Gameplay, GameplayState};
+use crate::components::{Position, BoxSpot, Box, Wall};
+use crate::events::{GameEvent, MoveEvent};
+use crate::tile_grid::TileGrid;
 
-pub struct GameplayStateSystem {}
+// Re-evaluates the level whenever something moved and announces wins and losses.
+#[derive(Default)]
+pub struct GameplayStateSystem {
+    move_reader: Option<ReaderId<MoveEvent>>,
+}
 
 impl<'s> System<'s> for GameplayStateSystem {
     type SystemData = (
         Write<'s, Gameplay>,
+        Read<'s, EventChannel<MoveEvent>>,
+        Write<'s, EventChannel<GameEvent>>,
         ReadStorage<'s, Position>,
         ReadStorage<'s, Box>,
         ReadStorage<'s, BoxSpot>,
-        WriteStorage<'s, UiText>,
-        ReadExpect<'s, GameUi>
+        ReadStorage<'s, Wall>,
+        Read<'s, TileGrid>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut gameplay_state, positions, boxes, box_spots, mut ui_text, game_ui) = data;
+        let (mut gameplay, move_events, mut game_events, positions, boxes, box_spots, walls, grid) = data;
 
-        // update ui elements
-        if let Some(moves_count_ui) = ui_text.get_mut(game_ui.moves_element) {
-            moves_count_ui.text = format!("Moves: {}", gameplay_state.moves_count.to_string());
+        let moved = move_events
+            .read(self.move_reader.as_mut().unwrap())
+            .any(|event| matches!(event, MoveEvent::PlayerMoved(_) | MoveEvent::MoveUndone(_)));
+        if !moved {
+            return;
         }
 
-        if let Some(gameplay_state_ui) = ui_text.get_mut(game_ui.gameplay_state_element) {
-            gameplay_state_ui.text = format!("{}", gameplay_state.state.to_string());
-        }
+        let solved = (&box_spots, &positions).join().all(|(box_spot, position)| {
+            grid.at(*position)
+                .iter()
+                .filter_map(|&entity| boxes.get(entity))
+                .any(|the_box| the_box.colour == box_spot.colour)
+        });
+
+        let stuck = (&boxes, &positions).join().any(|(the_box, position)| {
+            let on_correct_spot = grid.at(*position)
+                .iter()
+                .filter_map(|&entity| box_spots.get(entity))
+                .any(|box_spot| box_spot.colour == the_box.colour);
+
+            !on_correct_spot && in_corner(*position, &grid, &walls)
+        });
+
+        let (state, event) = if solved {
+            (GameplayState::Won, GameEvent::LevelWon)
+        } else if stuck {
+            (GameplayState::Lost, GameEvent::LevelLost)
+        } else if gameplay.state == GameplayState::Lost {
+            // an undo freed the box
+            (GameplayState::Playing, GameEvent::Resumed)
+        } else {
+            return;
+        };
 
-        let boxes_by_position: HashMap<(u8, u8), &Box> = (&positions, &boxes)
-            .join()
-            .map(|t| ((t.0.x, t.0.y), t.1))
-            .collect::<HashMap<_,_>>();
-
-        for (box_spot, position) in (&box_spots, &positions).join() {
-            if let Some(the_box) = boxes_by_position.get(&(position.x, position.y)) {
-                if the_box.colour != box_spot.colour {
-                    return;
-                }
-            } else {
-                gameplay_state.state = GameplayState::Playing;
-                return;
-            }
+        if gameplay.transition(state) {
+            game_events.single_write(event);
         }
+    }
 
-        gameplay_state.state = GameplayState::Won;
+    fn setup(&mut self, world: &mut World) {
+        <Self as System<'_>>::SystemData::setup(world);
+        self.move_reader = Some(
+            world
+                .fetch_mut::<EventChannel<MoveEvent>>()
+                .register_reader(),
+        );
     }
-}
\ No newline at end of file
+}
+
+// a box with walls on two adjacent sides can never be pushed again
+fn in_corner(position: Position, grid: &TileGrid, walls: &ReadStorage<Wall>) -> bool {
+    let is_wall = |x: Option<u8>, y: Option<u8>| match (x, y) {
+        (Some(x), Some(y)) => grid.at(Position { x, y }).iter().any(|&entity| walls.contains(entity)),
+        _ => true,
+    };
+
+    let up = is_wall(Some(position.x), position.y.checked_add(1));
+    let down = is_wall(Some(position.x), position.y.checked_sub(1));
+    let left = is_wall(position.x.checked_sub(1), Some(position.y));
+    let right = is_wall(position.x.checked_add(1), Some(position.y));
+
+    (up || down) && (left || right)
+}
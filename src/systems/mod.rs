@@ -2,8 +2,18 @@ pub use self::movement::MovementSystem;
 pub use self::gameplay_state::GameplayStateSystem;
 pub use self::animation::AnimationSystem;
 pub use self::sound::SoundSystem;
+pub use self::music::MusicSystem;
+pub use self::game_ui::GameUiSystem;
+pub use self::progress::ProgressSystem;
+pub use self::stats::StatsSystem;
+pub use self::debug_overlay::DebugOverlaySystem;
 
 mod movement;
 mod gameplay_state;
 mod animation;
-mod sound;
\ No newline at end of file
+mod sound;
+mod music;
+mod game_ui;
+mod progress;
+mod stats;
+mod debug_overlay;
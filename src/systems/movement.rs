@@ -1,130 +1,197 @@
-use std::collections::HashMap;
+use std::iter;
 
-use amethyst::core::ecs::world::Index;
-use amethyst::core::ecs::Entities;
 use amethyst::core::shrev::EventChannel;
-use amethyst::core::{Transform};
+use amethyst::core::{Time, Transform};
 use amethyst::derive::SystemDesc;
 use amethyst::ecs::{
-    Join, Read, ReadStorage, ReaderId, System, SystemData, World, Write, WriteStorage,
+    Entity, Read, ReadExpect, ReadStorage, ReaderId, System, SystemData, World, Write, WriteExpect,
+    WriteStorage,
 };
-use amethyst::input::{InputEvent, StringBindings, VirtualKeyCode};
-use amethyst::shrev::EventIterator;
+use amethyst::input::{InputEvent, InputHandler, StringBindings, VirtualKeyCode};
 
-use crate::map::{MAP_HEIGHT, MAP_WIDTH, TILE_WIDTH};
+use crate::board::Board;
+use crate::map::{board_position, TILE_WIDTH};
 use crate::components::*;
-use crate::sokoban::Gameplay;
-use crate::events::{MoveEvent, EntityMoved};
-
-#[derive(Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-    Right,
-    Left,
+use crate::rules::step;
+use crate::sokoban::{CurrentState, Gameplay, GameplayState, MadeStep, MoveHistory, ReplayPlayback};
+use crate::events::{BoxLeftSpot, BoxPlacedOnSpot, BoxPushed, GameEvent, MoveEvent, MoveUndone, PlayerMoved};
+use crate::settings::Settings;
+use crate::tile_grid::TileGrid;
+
+// seconds before a held arrow key starts repeating, and between repeats
+const KEY_REPEAT_DELAY: f32 = 0.3;
+const KEY_REPEAT_INTERVAL: f32 = 0.12;
+
+fn key_code(direction: Direction) -> VirtualKeyCode {
+    match direction {
+        Direction::Up => VirtualKeyCode::Up,
+        Direction::Down => VirtualKeyCode::Down,
+        Direction::Right => VirtualKeyCode::Right,
+        Direction::Left => VirtualKeyCode::Left,
+    }
 }
 
-#[derive(SystemDesc)]
+struct HeldKey {
+    direction: Direction,
+    timer: f32,
+}
+
+#[derive(SystemDesc, Default)]
 pub struct MovementSystem {
     pub input_reader: Option<ReaderId<InputEvent<StringBindings>>>,
+    held_key: Option<HeldKey>,
+}
+
+impl MovementSystem {
+    fn repeated_direction(
+        &mut self,
+        input: &InputHandler<StringBindings>,
+        delta_seconds: f32,
+        key_repeat: bool,
+    ) -> Option<Direction> {
+        let held_key = self.held_key.as_mut()?;
+        if !key_repeat || !input.key_is_down(key_code(held_key.direction)) {
+            self.held_key = None;
+            return None;
+        }
+
+        held_key.timer -= delta_seconds;
+        if held_key.timer > 0. {
+            return None;
+        }
+
+        held_key.timer += KEY_REPEAT_INTERVAL;
+        Some(held_key.direction)
+    }
 }
 
 impl<'s> System<'s> for MovementSystem {
     type SystemData = (
         WriteStorage<'s, Transform>,
-        Entities<'s>,
         ReadStorage<'s, Player>,
         ReadStorage<'s, Movable>,
-        ReadStorage<'s, Immovable>,
+        ReadStorage<'s, Box>,
+        ReadStorage<'s, BoxSpot>,
         WriteStorage<'s, Position>,
         Read<'s, EventChannel<InputEvent<StringBindings>>>,
         Write<'s, Gameplay>,
         Write<'s, EventChannel<MoveEvent>>,
+        Write<'s, EventChannel<GameEvent>>,
+        Write<'s, ReplayPlayback>,
+        Write<'s, MoveHistory>,
+        Option<WriteExpect<'s, Board>>,
+        Write<'s, TileGrid>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+        ReadExpect<'s, Settings>,
+        Read<'s, CurrentState>,
     );
 
     fn run(
         &mut self,
-        (mut transforms, entities, players, movables, immovables, mut positions, input_events, mut gameplay, mut move_events): Self::SystemData,
+        (mut transforms, players, movables, boxes, box_spots, mut positions, input_events, mut gameplay, mut move_events, mut game_events, mut playback, mut history, mut board, mut grid, input, time, settings, current_state): Self::SystemData,
     ) {
-        let mut to_move = Vec::new();
-        let mov: HashMap<(u8, u8), Index> = (&entities, &movables, &positions)
-            .join()
-            .map(|t| ((t.2.x, t.2.y), t.0.id()))
-            .collect::<HashMap<_, _>>();
-        let immov: HashMap<(u8, u8), Index> = (&entities, &immovables, &positions)
-            .join()
-            .map(|t| ((t.2.x, t.2.y), t.0.id()))
-            .collect::<HashMap<_, _>>();
-
-        for (_player, position) in (&players, &mut positions).join() {
-            let event_iterator = input_events
-                .read(self.input_reader.as_mut().unwrap())
-                .into_iter();
-            if let Some(direction) = get_direction(event_iterator) {
-                let (start, end, is_x) = match direction {
-                    Direction::Up => (position.y, MAP_HEIGHT, false),
-                    Direction::Down => (position.y, 0, false),
-                    Direction::Right => (position.x, MAP_WIDTH, true),
-                    Direction::Left => (position.x, 0, true),
-                };
-
-                let range = if start < end {
-                    (start..=end).collect::<Vec<_>>()
-                } else {
-                    (end..=start).rev().collect::<Vec<_>>()
-                };
-
-                for x_or_y in range {
-                    let pos = if is_x {
-                        (x_or_y, position.y)
-                    } else {
-                        (position.x, x_or_y)
-                    };
-
-                    match mov.get(&pos) {
-                        Some(id) => to_move.push((direction, id.clone())),
-                        None => match immov.get(&pos) {
-                            Some(_id) => {
-                                to_move.clear();
-                                move_events.single_write(MoveEvent::PlayerHitObstacle);
-                            },
-                            None => break,
-                        },
-                    }
-                }
+        let mut undo = false;
+        let mut pressed = None;
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            match event {
+                InputEvent::ActionPressed(action) if action == "undo" => undo = true,
+                event => pressed = pressed.or_else(|| get_direction(event)),
             }
         }
 
-        if to_move.len() > 0 {
-            gameplay.moves_count += 1;
+        let direction = match pressed {
+            Some(direction) => {
+                self.held_key = Some(HeldKey {
+                    direction,
+                    timer: KEY_REPEAT_DELAY,
+                });
+                Some(direction)
+            }
+            None => self.repeated_direction(&input, time.delta_seconds(), settings.gameplay.key_repeat),
+        };
+
+        // the board only takes input while the game is running, not in menus or the editor
+        let board = match board.as_mut() {
+            Some(board) if *current_state == CurrentState::Level => board,
+            _ => return,
+        };
+
+        // taking back the step that got a box stuck is the way out of a lost level
+        if undo && matches!(gameplay.state, GameplayState::Playing | GameplayState::Lost) {
+            if let Some(made) = history.steps.pop() {
+                for &(entity, from, to) in made.moved.iter().rev() {
+                    move_entity(entity, to, from, &mut transforms, &mut positions, &mut grid);
+                }
+                **board = made.board;
+                move_events.single_write(MoveEvent::MoveUndone(MoveUndone { pushed: made.pushed }));
+            }
+            return;
         }
 
-        for (direction, id) in to_move {
-            let transform = transforms.get_mut(entities.entity(id));
-            let position = positions.get_mut(entities.entity(id));
-            if let Some(transform) = transform {
-                match direction {
-                    Direction::Up => {
-                        transform.prepend_translation_y(TILE_WIDTH);
-                        position.unwrap().y += 1;
-                    }
-                    Direction::Down => {
-                        transform.prepend_translation_y(-TILE_WIDTH);
-                        position.unwrap().y -= 1;
-                    }
-                    Direction::Right => {
-                        transform.prepend_translation_x(TILE_WIDTH);
-                        position.unwrap().x += 1;
-                    }
-                    Direction::Left => {
-                        transform.prepend_translation_x(-TILE_WIDTH);
-                        position.unwrap().x -= 1;
-                    }
-                };
+        let direction = match gameplay.state {
+            GameplayState::Playing => direction,
+            // a replay makes its own moves, the player takes over once it has run out
+            GameplayState::Replaying if playback.is_finished() => {
+                if gameplay.transition(GameplayState::Playing) {
+                    game_events.single_write(GameEvent::Resumed);
+                }
+                None
             }
+            GameplayState::Replaying => playback.next(time.delta_seconds()),
+            _ => None,
+        };
+        let direction = match direction {
+            Some(direction) => direction,
+            None => return,
+        };
 
-            move_events.single_write(MoveEvent::EntityMoved(EntityMoved { id }))
+        let before = (**board).clone();
+        let step = match step(board, direction, settings.gameplay.rules) {
+            Ok(step) => step,
+            Err(_) => {
+                move_events.single_write(MoveEvent::PlayerHitObstacle);
+                return;
+            }
+        };
+
+        // in the order `step` moved them, the furthest box first and the player last, each
+        // looked up before anything moves
+        let moved = step
+            .pushed
+            .iter()
+            .copied()
+            .chain(iter::once((step.from, step.to)))
+            .filter_map(|(from, to)| {
+                let (from, to) = (board_position(board, from), board_position(board, to));
+                grid.at(from)
+                    .iter()
+                    .find(|&&entity| movables.contains(entity))
+                    .map(|&entity| (entity, from, to))
+            })
+            .collect::<Vec<_>>();
+
+        for &(entity, from, to) in &moved {
+            move_entity(entity, from, to, &mut transforms, &mut positions, &mut grid);
+
+            if players.contains(entity) {
+                move_events.single_write(MoveEvent::PlayerMoved(PlayerMoved {
+                    entity,
+                    from,
+                    to,
+                    direction,
+                }));
+            } else if let Some(the_box) = boxes.get(entity) {
+                move_events.single_write(MoveEvent::BoxPushed(BoxPushed { entity, from, to }));
+                write_spot_events(entity, the_box, from, to, &grid, &box_spots, &mut move_events);
+            }
         }
+
+        history.steps.push(MadeStep {
+            board: before,
+            moved,
+            pushed: step.is_push(),
+        });
     }
 
     fn setup(&mut self, world: &mut World) {
@@ -137,28 +204,68 @@ impl<'s> System<'s> for MovementSystem {
     }
 }
 
-fn get_direction(events: EventIterator<InputEvent<StringBindings>>) -> Option<Direction> {
-    for event in events {
-        return match event {
-            InputEvent::KeyPressed {
-                key_code: VirtualKeyCode::Up,
-                scancode: 103,
-            } => Some(Direction::Up),
-            InputEvent::KeyPressed {
-                key_code: VirtualKeyCode::Down,
-                scancode: 108,
-            } => Some(Direction::Down),
-            InputEvent::KeyPressed {
-                key_code: VirtualKeyCode::Right,
-                scancode: 106,
-            } => Some(Direction::Right),
-            InputEvent::KeyPressed {
-                key_code: VirtualKeyCode::Left,
-                scancode: 105,
-            } => Some(Direction::Left),
-            _ => None,
-        };
+fn move_entity(
+    entity: Entity,
+    from: Position,
+    to: Position,
+    transforms: &mut WriteStorage<Transform>,
+    positions: &mut WriteStorage<Position>,
+    grid: &mut TileGrid,
+) {
+    if let Some(transform) = transforms.get_mut(entity) {
+        transform.prepend_translation_x((to.x as f32 - from.x as f32) * TILE_WIDTH);
+        transform.prepend_translation_y((to.y as f32 - from.y as f32) * TILE_WIDTH);
+    }
+    if let Some(position) = positions.get_mut(entity) {
+        *position = to;
+    }
+    grid.move_entity(entity, from, to);
+}
+
+fn get_direction(event: &InputEvent<StringBindings>) -> Option<Direction> {
+    match event {
+        InputEvent::KeyPressed {
+            key_code: VirtualKeyCode::Up,
+            scancode: 103,
+        } => Some(Direction::Up),
+        InputEvent::KeyPressed {
+            key_code: VirtualKeyCode::Down,
+            scancode: 108,
+        } => Some(Direction::Down),
+        InputEvent::KeyPressed {
+            key_code: VirtualKeyCode::Right,
+            scancode: 106,
+        } => Some(Direction::Right),
+        InputEvent::KeyPressed {
+            key_code: VirtualKeyCode::Left,
+            scancode: 105,
+        } => Some(Direction::Left),
+        _ => None,
     }
+}
+
+fn write_spot_events(
+    entity: Entity,
+    the_box: &Box,
+    from: Position,
+    to: Position,
+    grid: &TileGrid,
+    box_spots: &ReadStorage<BoxSpot>,
+    move_events: &mut EventChannel<MoveEvent>,
+) {
+    let spot_at = |position: Position| {
+        grid.at(position)
+            .iter()
+            .find_map(|&entity| box_spots.get(entity))
+    };
 
-    None
+    if spot_at(from).is_some() {
+        move_events.single_write(MoveEvent::BoxLeftSpot(BoxLeftSpot { entity }));
+    }
+    if let Some(box_spot) = spot_at(to) {
+        move_events.single_write(MoveEvent::BoxPlacedOnSpot(BoxPlacedOnSpot {
+            entity,
+            correct: box_spot.colour == the_box.colour,
+        }));
+    }
 }
@@ -0,0 +1,59 @@
+use std::mem;
+
+use amethyst::audio::output::Output;
+use amethyst::audio::AudioSink;
+use amethyst::core::ecs::{Read, ReadExpect, System, Write};
+use amethyst::core::Time;
+
+use crate::music::Music;
+use crate::settings::Settings;
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+
+// Keeps the music volume in line with the settings and crossfades between playlists: the old
+// sink keeps playing here while it fades out, and `DjSystem` fills a fresh one that fades in.
+#[derive(Default)]
+pub struct MusicSystem {
+    outgoing: Option<AudioSink>,
+    fade: f32,
+}
+
+impl<'s> System<'s> for MusicSystem {
+    type SystemData = (
+        Write<'s, Music>,
+        Option<Write<'s, AudioSink>>,
+        Option<Read<'s, Output>>,
+        ReadExpect<'s, Settings>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut music, sink, output, settings, time): Self::SystemData) {
+        let (mut sink, output) = match (sink, output) {
+            (Some(sink), Some(output)) => (sink, output),
+            _ => return,
+        };
+
+        if music.changed {
+            music.changed = false;
+
+            let previous = mem::replace(&mut *sink, AudioSink::new(&output));
+            // a track that was still fading in is simply cut off
+            if self.outgoing.is_none() {
+                self.outgoing = Some(previous);
+            }
+            self.fade = 0.;
+        }
+
+        self.fade = (self.fade + time.delta_seconds() / CROSSFADE_SECONDS).min(1.);
+
+        let volume = settings.audio.music();
+        sink.set_volume(volume * self.fade);
+        if let Some(outgoing) = self.outgoing.as_mut() {
+            outgoing.set_volume(volume * (1. - self.fade));
+        }
+
+        if self.fade >= 1. {
+            self.outgoing = None;
+        }
+    }
+}
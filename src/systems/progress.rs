@@ -0,0 +1,49 @@
+use amethyst::core::ecs::{Read, ReaderId, System, SystemData, World, Write};
+use amethyst::core::shrev::EventChannel;
+
+use crate::events::GameEvent;
+use crate::level::LevelPacks;
+use crate::progress::Progress;
+use crate::sokoban::Gameplay;
+
+#[derive(Default)]
+pub struct ProgressSystem {
+    game_reader: Option<ReaderId<GameEvent>>,
+}
+
+impl<'s> System<'s> for ProgressSystem {
+    type SystemData = (
+        Read<'s, EventChannel<GameEvent>>,
+        Read<'s, Gameplay>,
+        Read<'s, LevelPacks>,
+        Write<'s, Progress>,
+    );
+
+    fn run(&mut self, (game_events, gameplay, packs, mut progress): Self::SystemData) {
+        for event in game_events.read(self.game_reader.as_mut().unwrap()) {
+            let level = match gameplay.level {
+                Some(level) => level,
+                None => continue,
+            };
+
+            match event {
+                GameEvent::LevelStarted => progress.mark_played(&packs, level),
+                GameEvent::LevelWon if !gameplay.replayed => progress.mark_solved(&packs, level),
+                _ => continue,
+            }
+
+            if let Err(e) = progress.save() {
+                log::error!("could not save progress: {}", e);
+            }
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        <Self as System<'_>>::SystemData::setup(world);
+        self.game_reader = Some(
+            world
+                .fetch_mut::<EventChannel<GameEvent>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use amethyst::core::ecs::{Read, ReaderId, System, SystemData, World, Write};
+use amethyst::core::shrev::EventChannel;
+
+use crate::events::{MoveEvent, MoveUndone};
+use crate::sokoban::Gameplay;
+
+#[derive(Default)]
+pub struct StatsSystem {
+    move_reader: Option<ReaderId<MoveEvent>>,
+}
+
+impl<'s> System<'s> for StatsSystem {
+    type SystemData = (Read<'s, EventChannel<MoveEvent>>, Write<'s, Gameplay>);
+
+    fn run(&mut self, (move_events, mut gameplay): Self::SystemData) {
+        // a step pushing a row of boxes still counts as a single push
+        let mut pushing = false;
+        for event in move_events.read(self.move_reader.as_mut().unwrap()) {
+            match event {
+                MoveEvent::PlayerMoved(_) => {
+                    gameplay.moves_count += 1;
+                    pushing = false;
+                }
+                MoveEvent::BoxPushed(_) if !pushing => {
+                    gameplay.pushes_count += 1;
+                    pushing = true;
+                }
+                MoveEvent::MoveUndone(MoveUndone { pushed }) => {
+                    gameplay.moves_count = gameplay.moves_count.saturating_sub(1);
+                    if *pushed {
+                        gameplay.pushes_count = gameplay.pushes_count.saturating_sub(1);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        <Self as System<'_>>::SystemData::setup(world);
+        self.move_reader = Some(
+            world
+                .fetch_mut::<EventChannel<MoveEvent>>()
+                .register_reader(),
+        );
+    }
+}
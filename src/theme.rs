@@ -0,0 +1,128 @@
+use amethyst::animation::AnimationControlSet;
+use amethyst::assets::{Handle, Prefab};
+use amethyst::core::ecs::{DispatcherBuilder, Join, World, WorldExt};
+use amethyst::error::Error;
+use amethyst::renderer::bundle::RenderPlan;
+use amethyst::renderer::{Backend, Factory, RenderPlugin, RenderToWindow, SpriteRender};
+use amethyst::ui::UiText;
+
+use crate::assets::{current_theme, GameAssets};
+use crate::components::Tile;
+use crate::settings::{Settings, SettingsFiles};
+use crate::sokoban::{AnimationId, GameUi, MyPrefabData};
+
+// The colour the window is cleared to, taken from the current theme.
+pub struct ClearColour(pub [f32; 4]);
+
+// Swaps the sprites, animations and UI colours of everything on screen over to the theme picked in
+// the settings. Menus restyle themselves when they are rebuilt.
+pub fn apply_theme(world: &mut World) {
+    let theme = current_theme(world);
+    world.insert(ClearColour(theme.background));
+
+    {
+        let entities = world.entities();
+        let tiles = world.read_storage::<Tile>();
+        let mut sprites = world.write_storage::<SpriteRender>();
+        let mut prefabs = world.write_storage::<Handle<Prefab<MyPrefabData>>>();
+        let mut control_sets =
+            world.write_storage::<AnimationControlSet<AnimationId, SpriteRender>>();
+
+        for (entity, tile) in (&entities, &tiles).join() {
+            sprites.insert(entity, theme.sprite(&tile.name)).unwrap();
+
+            if let Some(prefab) = theme.prefab(&tile.name) {
+                if prefabs.get(entity) != Some(&prefab) {
+                    // the prefab loader only reacts to inserted handles, and the animation
+                    // has to be started again from the new prefab
+                    prefabs.remove(entity);
+                    prefabs.insert(entity, prefab).unwrap();
+                    control_sets.remove(entity);
+                }
+            }
+        }
+    }
+
+    if let Some(game_ui) = world.try_fetch::<GameUi>() {
+        let mut texts = world.write_storage::<UiText>();
+        for entity in &[game_ui.moves_element, game_ui.gameplay_state_element] {
+            if let Some(text) = texts.get_mut(*entity) {
+                text.font = theme.font.clone();
+                text.color = theme.text_colour;
+            }
+        }
+    }
+}
+
+// Switches to the next (or previous) theme and remembers it in the user settings.
+pub fn cycle_theme(world: &mut World, step: i32) {
+    {
+        let names = world
+            .read_resource::<GameAssets>()
+            .themes
+            .iter()
+            .map(|theme| theme.name.clone())
+            .collect::<Vec<_>>();
+        let mut settings = world.write_resource::<Settings>();
+        let current = names
+            .iter()
+            .position(|name| *name == settings.display.theme)
+            .unwrap_or(0) as i32;
+        let next = (current + step).rem_euclid(names.len() as i32);
+        settings.display.theme = names[next as usize].clone();
+
+        if let Err(e) = world.read_resource::<SettingsFiles>().save(&settings) {
+            log::error!("could not save settings: {}", e);
+        }
+    }
+
+    apply_theme(world);
+}
+
+// `RenderToWindow` with a clear colour that follows the `ClearColour` resource. The render graph
+// is rebuilt whenever the colour changes.
+#[derive(Debug)]
+pub struct ThemedWindow {
+    window: RenderToWindow,
+    clear: [f32; 4],
+}
+
+impl ThemedWindow {
+    pub fn new(window: RenderToWindow, clear: [f32; 4]) -> Self {
+        ThemedWindow {
+            window: window.with_clear(clear),
+            clear,
+        }
+    }
+}
+
+impl<B: Backend> RenderPlugin<B> for ThemedWindow {
+    fn on_build<'a, 'b>(
+        &mut self,
+        world: &mut World,
+        builder: &mut DispatcherBuilder<'a, 'b>,
+    ) -> Result<(), Error> {
+        RenderPlugin::<B>::on_build(&mut self.window, world, builder)
+    }
+
+    fn should_rebuild(&mut self, world: &World) -> bool {
+        if let Some(clear) = world.try_fetch::<ClearColour>() {
+            if clear.0 != self.clear {
+                // the window itself was opened in on_build, the replacement only plans the pass
+                self.clear = clear.0;
+                self.window = RenderToWindow::default().with_clear(self.clear);
+            }
+        }
+
+        RenderPlugin::<B>::should_rebuild(&mut self.window, world)
+    }
+
+    fn on_plan(
+        &mut self,
+        plan: &mut RenderPlan<B>,
+        factory: &mut Factory<B>,
+        world: &World,
+    ) -> Result<(), Error> {
+        RenderPlugin::<B>::on_plan(&mut self.window, plan, factory, world)
+    }
+}
@@ -0,0 +1,67 @@
+use amethyst::core::ecs::{Entities, Entity, Join, ReadStorage};
+
+use crate::components::Position;
+
+// Entities per map cell, so systems can look up what is on a tile without scanning every
+// position. Built when a level is loaded and updated by whoever moves an entity.
+#[derive(Default)]
+pub struct TileGrid {
+    width: u8,
+    height: u8,
+    cells: Vec<Vec<Entity>>,
+}
+
+impl TileGrid {
+    pub fn new(width: u8, height: u8) -> Self {
+        TileGrid {
+            width,
+            height,
+            cells: vec![Vec::new(); width as usize * height as usize],
+        }
+    }
+
+    pub fn from_positions(
+        width: u8,
+        height: u8,
+        entities: &Entities,
+        positions: &ReadStorage<Position>,
+    ) -> Self {
+        let mut grid = TileGrid::new(width, height);
+        for (entity, position) in (entities, positions).join() {
+            grid.insert(entity, *position);
+        }
+        grid
+    }
+
+    fn index(&self, position: Position) -> Option<usize> {
+        if position.x < self.width && position.y < self.height {
+            Some(position.y as usize * self.width as usize + position.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn at(&self, position: Position) -> &[Entity] {
+        match self.index(position) {
+            Some(index) => &self.cells[index],
+            None => &[],
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Position) {
+        if let Some(index) = self.index(position) {
+            self.cells[index].push(entity);
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity, position: Position) {
+        if let Some(index) = self.index(position) {
+            self.cells[index].retain(|&other| other != entity);
+        }
+    }
+
+    pub fn move_entity(&mut self, entity: Entity, from: Position, to: Position) {
+        self.remove(entity, from);
+        self.insert(entity, to);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use crate::board::{self, Board, BoxColour, Cell, LevelError};
+use crate::level::LevelPack;
+
+// Tiled stores flipped and rotated tiles in the top bits of the id.
+const GID_MASK: u32 = 0x0fff_ffff;
+
+#[derive(Debug)]
+pub enum TiledError {
+    Read { file: String, message: String },
+    Missing { element: String, attribute: String },
+    Unsupported(String),
+    UnmappedTile { gid: u32, x: usize, y: usize },
+    UnknownType { what: String, kind: String },
+    UnknownColour { what: String, colour: String },
+    OutOfBounds { what: String, x: f64, y: f64 },
+    Level(LevelError),
+}
+
+impl fmt::Display for TiledError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TiledError::Read { file, message } => write!(fmt, "could not read {}: {}", file, message),
+            TiledError::Missing { element, attribute } => {
+                write!(fmt, "<{}> has no {} attribute", element, attribute)
+            }
+            TiledError::Unsupported(what) => write!(fmt, "{} is not supported", what),
+            TiledError::UnmappedTile { gid, x, y } => write!(
+                fmt,
+                "tile id {} at {}, {} has no type, give the tile a `type` property in the tileset",
+                gid, x, y
+            ),
+            TiledError::UnknownType { what, kind } => write!(
+                fmt,
+                "{} has unknown type {:?}, expected floor, wall, box, spot or player",
+                what, kind
+            ),
+            TiledError::UnknownColour { what, colour } => {
+                write!(fmt, "{} has unknown colour {:?}", what, colour)
+            }
+            TiledError::OutOfBounds { what, x, y } => {
+                write!(fmt, "{} at {}, {} is outside the map", what, x, y)
+            }
+            TiledError::Level(error) => error.fmt(fmt),
+        }
+    }
+}
+
+impl Error for TiledError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Floor,
+    Wall,
+    Box(BoxColour),
+    Spot(BoxColour),
+    Player,
+}
+
+// Reads an orthogonal Tiled map as a single level. Tiles are mapped through a `type` property
+// (or the tile's class) in the tileset: `floor`, `wall`, `box`, `spot` or `player`, with an
+// optional `colour` property of `blue` (the default) or `red`. Object layers use the same types
+// on the objects themselves. Tile layers must be saved as CSV or XML; later layers are drawn over
+// earlier ones and objects over all tile layers. Blank cells inside the walls become floor.
+//
+// The map's `title`, `author` and `copyright` properties are kept for the level select.
+pub fn load(name: String, path: &Path, contents: &str) -> Result<LevelPack, TiledError> {
+    let document = parse_document(path, contents)?;
+    let map = document.root_element();
+    if map.attribute("orientation").is_some_and(|orientation| orientation != "orthogonal") {
+        return Err(TiledError::Unsupported(format!(
+            "{} orientation",
+            map.attribute("orientation").unwrap_or_default()
+        )));
+    }
+    if map.attribute("infinite") == Some("1") {
+        return Err(TiledError::Unsupported("an infinite map".to_string()));
+    }
+
+    let width = number(map, "width")? as usize;
+    let height = number(map, "height")? as usize;
+    board::check_size(width, height).map_err(TiledError::Level)?;
+    let tile_width = number(map, "tilewidth")?;
+    let tile_height = number(map, "tileheight")?;
+
+    let mut tiles = HashMap::new();
+    for tileset in map.children().filter(|node| node.has_tag_name("tileset")) {
+        let first_gid = number(tileset, "firstgid")? as u32;
+        match tileset.attribute("source") {
+            Some(source) => {
+                let tsx_path = path.parent().unwrap_or_else(|| Path::new("")).join(source);
+                let tsx = fs::read_to_string(&tsx_path).map_err(|e| TiledError::Read {
+                    file: tsx_path.display().to_string(),
+                    message: e.to_string(),
+                })?;
+                let document = parse_document(&tsx_path, &tsx)?;
+                read_tileset(document.root_element(), first_gid, &mut tiles)?;
+            }
+            None => read_tileset(tileset, first_gid, &mut tiles)?,
+        }
+    }
+
+    let mut board = Board::new(width, height);
+    for layer in map.children().filter(|node| node.has_tag_name("layer")) {
+        for (index, gid) in layer_gids(layer, width)?.into_iter().enumerate() {
+            if gid == 0 || index >= board.cells.len() {
+                continue;
+            }
+            let kind = tiles.get(&gid).ok_or_else(|| TiledError::UnmappedTile {
+                gid,
+                x: index % width,
+                y: index / width,
+            })?;
+            place(&mut board, index, *kind).map_err(TiledError::Level)?;
+        }
+    }
+
+    for group in map.children().filter(|node| node.has_tag_name("objectgroup")) {
+        for object in group.children().filter(|node| node.has_tag_name("object")) {
+            let what = format!("object {}", object.attribute("id").unwrap_or("?"));
+            let gid = object
+                .attribute("gid")
+                .and_then(|gid| gid.parse::<u32>().ok())
+                .map(|gid| gid & GID_MASK);
+
+            // tile objects are anchored at their bottom left corner
+            let x = number(object, "x")?;
+            let mut y = number(object, "y")?;
+            if gid.is_some() {
+                y -= tile_height;
+            }
+            let (column, row) = ((x / tile_width).floor(), (y / tile_height).floor());
+            if column < 0. || row < 0. || column >= width as f64 || row >= height as f64 {
+                return Err(TiledError::OutOfBounds { what, x, y });
+            }
+            let (column, row) = (column as usize, row as usize);
+
+            let kind = match (object_type(object), gid) {
+                (Some(kind), _) => kind_of(&what, kind, property(object, "colour"))?,
+                (None, Some(gid)) => *tiles.get(&gid).ok_or(TiledError::UnmappedTile {
+                    gid,
+                    x: column,
+                    y: row,
+                })?,
+                (None, None) => {
+                    return Err(TiledError::UnknownType {
+                        what,
+                        kind: String::new(),
+                    })
+                }
+            };
+            let index = board.index(column, row);
+            place(&mut board, index, kind).map_err(TiledError::Level)?;
+        }
+    }
+
+    board.fill_floor();
+
+    let mut pack = LevelPack {
+        title: property(map, "title"),
+        author: property(map, "author"),
+        copyright: property(map, "copyright"),
+        ..Default::default()
+    };
+    let title = pack.title.clone().or_else(|| Some(name.clone()));
+    let copyright = pack.copyright.clone();
+    pack.name = name;
+    pack.add_board(title, copyright, board);
+
+    Ok(pack)
+}
+
+fn parse_document<'a>(path: &Path, contents: &'a str) -> Result<Document<'a>, TiledError> {
+    Document::parse(contents).map_err(|e| TiledError::Read {
+        file: path.display().to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn read_tileset(
+    tileset: Node,
+    first_gid: u32,
+    tiles: &mut HashMap<u32, Kind>,
+) -> Result<(), TiledError> {
+    let name = tileset.attribute("name").unwrap_or("tileset");
+    for tile in tileset.children().filter(|node| node.has_tag_name("tile")) {
+        let id = number(tile, "id")? as u32;
+        let what = format!("tile {} of {}", id, name);
+        if let Some(kind) = object_type(tile) {
+            tiles.insert(first_gid + id, kind_of(&what, kind, property(tile, "colour"))?);
+        }
+    }
+    Ok(())
+}
+
+// The tile ids of a layer in row order, 0 for an empty cell.
+fn layer_gids(layer: Node, width: usize) -> Result<Vec<u32>, TiledError> {
+    let data = match layer.children().find(|node| node.has_tag_name("data")) {
+        Some(data) => data,
+        None => return Ok(Vec::new()),
+    };
+
+    if data.attribute("compression").is_some() {
+        return Err(TiledError::Unsupported(
+            "compressed layer data, save the map with CSV layers".to_string(),
+        ));
+    }
+
+    let parse = |index: usize, gid: &str| {
+        gid.parse::<u32>().map_err(|_| {
+            TiledError::Level(LevelError::UnknownTile {
+                row: index / width,
+                column: index % width,
+                tile: gid.to_string(),
+            })
+        })
+    };
+
+    let gids = match data.attribute("encoding") {
+        Some("csv") => {
+            let text = data.text().unwrap_or("").trim();
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                text.split(',')
+                    .enumerate()
+                    .map(|(index, gid)| parse(index, gid.trim()))
+                    .collect::<Result<Vec<u32>, _>>()?
+            }
+        }
+        // a tile without a gid is an empty cell
+        None => data
+            .children()
+            .filter(|node| node.has_tag_name("tile"))
+            .enumerate()
+            .map(|(index, tile)| tile.attribute("gid").map_or(Ok(0), |gid| parse(index, gid)))
+            .collect::<Result<Vec<u32>, _>>()?,
+        Some(encoding) => {
+            return Err(TiledError::Unsupported(format!(
+                "{} layer data, save the map with CSV layers",
+                encoding
+            )))
+        }
+    };
+
+    Ok(gids.into_iter().map(|gid| gid & GID_MASK).collect())
+}
+
+// `type` before Tiled 1.9, `class` after, or a `type` property
+fn object_type(node: Node) -> Option<String> {
+    node.attribute("type")
+        .or_else(|| node.attribute("class"))
+        .filter(|kind| !kind.is_empty())
+        .map(str::to_string)
+        .or_else(|| property(node, "type"))
+}
+
+fn kind_of(what: &str, kind: String, colour: Option<String>) -> Result<Kind, TiledError> {
+    let colour = match colour.as_deref() {
+        None | Some("blue") => BoxColour::Blue,
+        Some("red") => BoxColour::Red,
+        Some(other) => {
+            return Err(TiledError::UnknownColour {
+                what: what.to_string(),
+                colour: other.to_string(),
+            })
+        }
+    };
+
+    Ok(match kind.to_lowercase().as_str() {
+        "floor" => Kind::Floor,
+        "wall" => Kind::Wall,
+        "box" => Kind::Box(colour),
+        "spot" | "goal" => Kind::Spot(colour),
+        "player" => Kind::Player,
+        _ => {
+            return Err(TiledError::UnknownType {
+                what: what.to_string(),
+                kind,
+            })
+        }
+    })
+}
+
+// a level has one player, placing it on a second cell is an error
+fn place(board: &mut Board, index: usize, kind: Kind) -> Result<(), LevelError> {
+    match kind {
+        Kind::Floor => board.cells[index] = Cell::Floor,
+        Kind::Wall => {
+            board.cells[index] = Cell::Wall;
+            board.boxes[index] = None;
+            board.goals[index] = None;
+        }
+        Kind::Box(colour) => {
+            board.cells[index] = Cell::Floor;
+            board.boxes[index] = Some(colour);
+        }
+        Kind::Spot(colour) => {
+            board.cells[index] = Cell::Floor;
+            board.goals[index] = Some(colour);
+        }
+        Kind::Player => {
+            if board.player.is_some_and(|player| player != index) {
+                return Err(LevelError::MultiplePlayers);
+            }
+            board.cells[index] = Cell::Floor;
+            board.player = Some(index);
+        }
+    }
+    Ok(())
+}
+
+fn property(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name("properties"))?
+        .children()
+        .find(|child| child.has_tag_name("property") && child.attribute("name") == Some(name))
+        .and_then(|property| property.attribute("value").or_else(|| property.text()))
+        .map(str::to_string)
+}
+
+fn number(node: Node, attribute: &str) -> Result<f64, TiledError> {
+    node.attribute(attribute)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| TiledError::Missing {
+            element: node.tag_name().name().to_string(),
+            attribute: attribute.to_string(),
+        })
+}